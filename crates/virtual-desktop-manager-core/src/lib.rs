@@ -10,6 +10,10 @@ extern crate native_windows_gui as nwg;
 
 use crate::tray::TrayPlugin;
 
+pub use virtual_desktop_manager_filters::{
+    filter_lint, filter_matcher, filter_plan, window_filter,
+};
+
 #[cfg(feature = "auto_start")]
 mod auto_start;
 pub mod block_on;
@@ -22,7 +26,6 @@ mod quick_switch;
 pub mod settings;
 pub mod tray;
 pub mod vd;
-pub mod window_filter;
 pub mod window_info;
 #[cfg(all(feature = "logging", debug_assertions))]
 mod wm_msg_to_string;
@@ -124,7 +127,16 @@ struct FilterSourceArgs {
 }
 #[cfg(feature = "cli_commands")]
 impl FilterSourceArgs {
-    fn load_filters(&self) -> Result<Vec<window_filter::WindowFilter>, Box<dyn std::error::Error>> {
+    /// Load filters and settings for filter groups.
+    fn load_filters(
+        &self,
+    ) -> Result<
+        (
+            Vec<window_filter::WindowFilter>,
+            Vec<window_filter::FilterGroup>,
+        ),
+        Box<dyn std::error::Error>,
+    > {
         if let Some(exported_filter) = &self.exported_filter {
             let data = std::fs::read_to_string(exported_filter)?;
             let is_legacy = exported_filter.extension().is_some_and(|ext| {
@@ -134,7 +146,10 @@ impl FilterSourceArgs {
             if is_legacy {
                 #[cfg(feature = "persist_filters_xml")]
                 {
-                    Ok(window_filter::WindowFilter::deserialize_from_xml(&data)?)
+                    Ok((
+                        window_filter::WindowFilter::deserialize_from_xml(&data)?,
+                        Vec::new(),
+                    ))
                 }
                 #[cfg(not(feature = "persist_filters_xml"))]
                 {
@@ -159,7 +174,7 @@ impl FilterSourceArgs {
                             serde_path_to_error::deserialize(&mut deserializer)
                         }
                     };
-                    Ok(result?.migrate_and_get_filters())
+                    Ok(result?.migrate_and_get_filters_with_groups())
                 }
                 #[cfg(not(feature = "persist_filters"))]
                 {
@@ -172,33 +187,17 @@ impl FilterSourceArgs {
                 }
             }
         } else {
-            let config_path;
-            let config_path = match &self.config {
-                Some(v) => v,
-                None => {
-                    config_path = std::env::current_exe()?.with_extension("settings.json");
-                    &config_path
-                }
-            };
             #[cfg(feature = "persist_settings")]
             {
-                let data = std::fs::read_to_string(&config_path)?;
-
-                let mut deserializer = serde_json::Deserializer::from_str(&data);
-                let result: Result<settings::UiSettingsFallback, _> = {
-                    #[cfg(not(feature = "serde_path_to_error"))]
-                    {
-                        serde::Deserialize::deserialize(&mut deserializer)
-                    }
-                    #[cfg(feature = "serde_path_to_error")]
-                    {
-                        serde_path_to_error::deserialize(&mut deserializer)
-                    }
-                };
-                Ok(result?
-                    .filters
-                    .ok_or("Failed to deserialize config file")?
-                    .to_vec())
+                let settings = self.load_config()?;
+                Ok((
+                    settings
+                        .filters
+                        .ok_or("Failed to deserialize config file")?
+                        .to_vec(),
+                    // Older config files don't have any groups:
+                    settings.filter_groups.unwrap_or_default().to_vec(),
+                ))
             }
             #[cfg(not(feature = "persist_settings"))]
             {
@@ -211,6 +210,43 @@ impl FilterSourceArgs {
             }
         }
     }
+    /// Load the windows that filters must never affect from the config file.
+    /// The default config file is used if filters are loaded from an exported
+    /// filters file.
+    fn load_exclusions(&self) -> std::sync::Arc<window_filter::WindowExclusions> {
+        #[cfg(feature = "persist_settings")]
+        match self.load_config() {
+            // Older config files don't have any exclusions:
+            Ok(settings) => return settings.window_exclusions.unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load window exclusions from config file")
+            }
+        }
+        std::sync::Arc::default()
+    }
+    /// Read the config file that was specified or the default one that is
+    /// next to the executable.
+    #[cfg(feature = "persist_settings")]
+    fn load_config(&self) -> Result<settings::UiSettingsFallback, Box<dyn std::error::Error>> {
+        let config_path = match &self.config {
+            Some(v) => v.clone(),
+            None => std::env::current_exe()?.with_extension("settings.json"),
+        };
+        let data = std::fs::read_to_string(&config_path)?;
+
+        let mut deserializer = serde_json::Deserializer::from_str(&data);
+        let result: Result<settings::UiSettingsFallback, _> = {
+            #[cfg(not(feature = "serde_path_to_error"))]
+            {
+                serde::Deserialize::deserialize(&mut deserializer)
+            }
+            #[cfg(feature = "serde_path_to_error")]
+            {
+                serde_path_to_error::deserialize(&mut deserializer)
+            }
+        };
+        Ok(result?)
+    }
 }
 
 #[cfg(feature = "cli_commands")]
@@ -253,6 +289,66 @@ enum Args {
         /// Stop all windows from flashing.
         #[clap(long)]
         stop_flashing: bool,
+
+        /// Only show what the filters would do without changing any windows.
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Only apply the filters in this group, even if the group is
+        /// disabled. By default filters in disabled groups are ignored.
+        #[clap(long)]
+        group: Option<String>,
+    },
+    /// Move windows to the current virtual desktop.
+    ///
+    /// By default this applies the filters with a "Bring here" action from the
+    /// same filters as the `apply-filters` command. Other filters still prevent
+    /// later filters from matching but don't affect any windows.
+    BringHere {
+        /// Where to find the filters that should be applied.
+        #[command(flatten)]
+        filter_file_source: FilterSourceArgs,
+
+        /// Bring all windows of the process with this name here instead of
+        /// using filters, for example `firefox`. The name is not case
+        /// sensitive.
+        #[clap(long)]
+        process: Option<String>,
+
+        /// Unpin windows of the process before moving them. Only used
+        /// together with `--process`.
+        #[clap(long, requires = "process")]
+        unpin: bool,
+
+        /// Prevent window from flashing if it was moved.
+        #[clap(long)]
+        stop_flashing_if_moved: bool,
+
+        /// Only show which windows would be moved without changing any.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Export filters to a JSON file that can be imported in the config window.
+    ExportFilters {
+        /// Where to find the filters that should be exported.
+        #[command(flatten)]
+        filter_file_source: FilterSourceArgs,
+
+        /// Only export the filters in this group.
+        #[clap(long)]
+        group: Option<String>,
+
+        /// Write the exported filters to this file instead of printing them.
+        #[clap(long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Check filters for problems such as filters that can never be used.
+    ///
+    /// Uses the same filters as the `apply-filters` command by default.
+    LintFilters {
+        /// Where to find the filters that should be checked.
+        #[command(flatten)]
+        filter_file_source: FilterSourceArgs,
     },
 }
 #[cfg(feature = "cli_commands")]
@@ -374,16 +470,133 @@ impl Args {
                 filter_file_source,
                 stop_flashing_if_moved,
                 stop_flashing,
+                dry_run,
+                group,
             } => {
-                let filters = filter_file_source
+                let (filters, groups) = filter_file_source
                     .load_filters()
                     .expect("Failed to load filters");
+                let filters = match &group {
+                    Some(group) => window_filter::FilterGroup::filters_in_group(&filters, group),
+                    None => window_filter::FilterGroup::enabled_filters(&groups, &filters),
+                };
 
-                tray_plugins::apply_filters::apply_filters(
-                    Some(filters.as_slice()),
+                let plan = tray_plugins::apply_filters::plan_filters(
+                    Some(&filter_matcher::FilterMatcher::new(filters.into())),
                     stop_flashing_if_moved,
                     stop_flashing,
+                    filter_file_source.load_exclusions(),
+                    // Filters have never been applied by this process:
+                    Default::default(),
                 );
+                if dry_run {
+                    tracing::info!(operations = ?plan.operations, "Planned filter operations");
+                    print!("{plan}");
+                } else {
+                    tray_plugins::apply_filters::execute_plan(&plan);
+                }
+            }
+            Args::BringHere {
+                filter_file_source,
+                process,
+                unpin,
+                stop_flashing_if_moved,
+                dry_run,
+            } => {
+                let filters = if let Some(process) = process {
+                    use window_filter::{
+                        FilterAction, TextMatchMode, TextMatchOptions, TextPattern, WindowFilter,
+                    };
+                    vec![WindowFilter {
+                        process_name: TextPattern::new(process.into())
+                            .with_mode(TextMatchMode::Exact)
+                            .with_options(TextMatchOptions {
+                                ignore_case: true,
+                                ..Default::default()
+                            }),
+                        action: if unpin {
+                            FilterAction::UnpinAndBringHere
+                        } else {
+                            FilterAction::BringHere
+                        },
+                        ..Default::default()
+                    }]
+                } else {
+                    let (filters, groups) = filter_file_source
+                        .load_filters()
+                        .expect("Failed to load filters");
+                    window_filter::WindowFilter::bring_here_filters(
+                        &window_filter::FilterGroup::enabled_filters(&groups, &filters),
+                    )
+                };
+
+                let plan = tray_plugins::apply_filters::plan_filters(
+                    Some(&filter_matcher::FilterMatcher::new(filters.into())),
+                    stop_flashing_if_moved,
+                    false,
+                    filter_file_source.load_exclusions(),
+                    // Filters have never been applied by this process:
+                    Default::default(),
+                );
+                if dry_run {
+                    tracing::info!(operations = ?plan.operations, "Planned filter operations");
+                    print!("{plan}");
+                } else {
+                    tray_plugins::apply_filters::execute_plan(&plan);
+                }
+            }
+            Args::ExportFilters {
+                filter_file_source,
+                group,
+                output,
+            } => {
+                let (filters, groups) = filter_file_source
+                    .load_filters()
+                    .expect("Failed to load filters");
+                #[cfg(feature = "persist_filters")]
+                {
+                    let exported = match &group {
+                        Some(group) => window_filter::ExportedWindowFilters::for_group(
+                            &filters, &groups, group,
+                        ),
+                        None => {
+                            window_filter::ExportedWindowFilters::new(filters).with_groups(&groups)
+                        }
+                    };
+                    let data = serde_json::to_string_pretty(&exported)
+                        .expect("Failed to convert filters to JSON");
+                    match output {
+                        Some(output) => {
+                            std::fs::write(output, data).expect("Failed to write exported filters")
+                        }
+                        None => println!("{data}"),
+                    }
+                }
+                #[cfg(not(feature = "persist_filters"))]
+                {
+                    let _ = (filters, groups, group, output);
+                    panic!(
+                        "This program was compiled without support for JSON filters/rules. \
+                        Recompile the program from source with the \"persist_filters\" feature \
+                        in order to support exporting such filter files."
+                    );
+                }
+            }
+            Args::LintFilters { filter_file_source } => {
+                let (filters, groups) = filter_file_source
+                    .load_filters()
+                    .expect("Failed to load filters");
+
+                let desktop_count = vd::get_desktop_count()
+                    .map_err(|e| tracing::warn!(error = %e, "Failed to get virtual desktop count"))
+                    .ok();
+                let warnings = filter_lint::lint_filters(&filters, &groups, desktop_count);
+                if warnings.is_empty() {
+                    println!("No problems found in {} filters", filters.len());
+                }
+                for warning in warnings {
+                    println!("{warning}");
+                }
             }
         }
     }
@@ -468,6 +681,7 @@ pub fn run_gui<G: ConfigWindowGui + Default>() {
             Box::<tray_plugins::menus::QuickSwitchMenuUiAdapter>::default(),
             Box::<tray_plugins::menus::FlatSwitchMenu>::default(),
             Box::<tray_plugins::menus::BottomMenuItems>::default(),
+            Box::<tray_plugins::menus::FilterGroupsMenu>::default(),
             Box::<G>::default(),
         ],
         |dyn_ui| Some(dyn_ui.get_ui::<G>()?),
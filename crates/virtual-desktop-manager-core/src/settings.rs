@@ -1,7 +1,7 @@
 use crate::{
     dynamic_gui::DynamicUiHooks,
     tray::{SystemTray, SystemTrayRef, TrayPlugin, TrayRoot},
-    window_filter::WindowFilter,
+    window_filter::{FilterGroup, WindowExclusions, WindowFilter},
 };
 #[cfg(feature = "persist_settings")]
 use serde::{Deserialize, Deserializer, Serialize};
@@ -303,10 +303,16 @@ default_deserialize!(
         /// Filters/rules that specify which windows should be moved and to what
         /// virtual desktop.
         pub filters: Arc<[WindowFilter]>,
+        /// Settings for named groups of filters. Groups that are only
+        /// mentioned by filters are enabled.
+        pub filter_groups: Arc<[FilterGroup]>,
+        /// Windows that are never moved or otherwise affected, even by filters
+        /// that match all windows.
+        pub window_exclusions: Arc<WindowExclusions>,
     }
 );
 impl UiSettings {
-    pub const CURRENT_VERSION: u64 = 2;
+    pub const CURRENT_VERSION: u64 = 4;
 
     /// Ensure settings are the newest version. Some work might have been done
     /// previously by [`UiSettingsFallback::maybe_migrate`] if initial parsing
@@ -325,6 +331,12 @@ impl UiSettingsFallback {
         {
             self.open_menu_at_mouse_pos_hotkey = Some(Arc::from(""));
         }
+        if self.filter_groups.is_none() && matches!(self.version, Some(v) if v <= 2) {
+            self.filter_groups = Some(Arc::new([]));
+        }
+        if self.window_exclusions.is_none() && matches!(self.version, Some(v) if v <= 3) {
+            self.window_exclusions = Some(Arc::default());
+        }
         self.has_all_fields()
     }
 }
@@ -351,6 +363,8 @@ impl Default for UiSettings {
 
             config_window: ConfigWindowInfo::default(),
             filters: Arc::new([]),
+            filter_groups: Arc::new([]),
+            window_exclusions: Arc::default(),
         }
     }
 }
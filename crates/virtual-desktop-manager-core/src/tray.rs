@@ -18,6 +18,7 @@ use crate::{
         windows_msg_for_explorer_restart, FastTimerControl, TrayWindow,
     },
     settings::{TrayClickAction, UiSettings},
+    tray_plugins::apply_filters::FilterSelection,
     vd, ConfigWindowGui,
 };
 
@@ -1028,16 +1029,25 @@ impl SystemTray {
     }
     pub fn apply_filters(&self) {
         tracing::info!("SystemTray::apply_filters()");
+        self.apply_specific_filters(FilterSelection::Enabled);
+    }
+    /// Apply the filters in a group even if that group is disabled.
+    pub fn apply_filter_group(&self, group: &str) {
+        tracing::info!(group, "SystemTray::apply_filter_group()");
+        self.apply_specific_filters(FilterSelection::Group(group));
+    }
+    /// Only apply filters with the "Bring here" actions, see
+    /// [`FilterSelection::BringHere`].
+    pub fn bring_windows_here(&self) {
+        tracing::info!("SystemTray::bring_windows_here()");
+        self.apply_specific_filters(FilterSelection::BringHere);
+    }
+    fn apply_specific_filters(&self, selection: FilterSelection<'_>) {
         if let Some(apply_filters) = self
             .get_dynamic_ui()
             .get_ui::<crate::tray_plugins::apply_filters::ApplyFilters>()
         {
-            let settings = self.settings().get();
-            let filters = settings.filters.clone();
-            apply_filters.apply_filters(
-                filters,
-                settings.stop_flashing_windows_after_applying_filter,
-            );
+            apply_filters.apply_filters(selection, &self.settings().get());
         } else {
             self.show_notification(
                 "Virtual Desktop Manager Warning",
@@ -1056,7 +1066,7 @@ impl SystemTray {
             .get_dynamic_ui()
             .get_ui::<crate::tray_plugins::apply_filters::ApplyFilters>();
         if let Some(background) = guard {
-            background.stop_all_flashing_windows();
+            background.stop_all_flashing_windows(self.settings().get().window_exclusions.clone());
         } else {
             self.show_notification(
                 "Virtual Desktop Manager Warning",
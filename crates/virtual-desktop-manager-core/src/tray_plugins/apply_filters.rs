@@ -1,130 +1,263 @@
 use crate::{
     dynamic_gui::DynamicUiHooks,
-    tray::{SystemTray, TrayPlugin},
+    filter_matcher::FilterMatcher,
+    filter_plan::{self, FilterPlan},
+    settings::UiSettings,
+    tray::{SystemTray, SystemTrayRef, TrayPlugin, TrayRoot},
     vd,
-    window_filter::{FilterAction, WindowFilter},
-    window_info::{VirtualDesktopInfo, WindowInfo},
+    window_filter::{
+        FilterAction, FilterContext, FilterGroup, SeenWindows, WindowExclusions, WindowFilter,
+    },
+    window_info::{self, Win32WindowController, WindowHandle, WindowInfo},
 };
 use nwd::NwgPartial;
 use std::{
     any::TypeId,
-    cell::OnceCell,
+    cell::{OnceCell, RefCell},
     ops::Deref,
     rc::Rc,
-    sync::{mpsc, Arc},
+    sync::{mpsc, Arc, Mutex},
     thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
 #[derive(Debug)]
 enum BackgroundAction {
     ApplyFilters {
-        filters: Arc<[WindowFilter]>,
+        filters: Arc<FilterMatcher>,
+        /// The user's normal enabled filters. Windows are checked against
+        /// these once they have settled, even if `filters` only contained
+        /// some of them, see [`WindowFilter::settle_delay_ms`].
+        recheck_filters: Arc<FilterMatcher>,
         stop_flashing_windows: bool,
+        exclusions: Arc<WindowExclusions>,
+    },
+    StopFlashingWindows {
+        exclusions: Arc<WindowExclusions>,
     },
-    StopFlashingWindows,
+    /// A window's title changed, so filters with a settle delay might need to
+    /// be checked again later.
+    WindowChanged(WindowHandle),
+}
+
+/// Information about what was changed by [`apply_filters`].
+#[derive(Debug, Default, Clone)]
+pub struct ApplyFiltersResult {
+    /// Virtual desktops that were created so that windows could be moved to
+    /// them.
+    pub created_desktops: Vec<CreatedDesktop>,
+    /// Windows that were moved by filters that requested a notification, see
+    /// [`WindowFilter::notify_on_move`].
+    pub notify_moved: Vec<MovedWindow>,
+    /// Filters should be applied again after this delay since some windows
+    /// haven't settled yet, see [`WindowFilter::settle_delay_ms`].
+    pub recheck_after: Option<Duration>,
+    /// Filters that were successfully applied to windows and should be
+    /// remembered, see [`WindowFilter::only_first_time`].
+    pub applied_filters: Vec<AppliedFilter>,
+}
+
+/// A filter whose action was successfully applied to a window.
+#[derive(Debug, Clone)]
+pub struct AppliedFilter {
+    /// Zero-based index of the filter.
+    pub filter_index: usize,
+    pub window: WindowHandle,
+    pub process_id: u32,
+}
+
+/// A virtual desktop that was created while applying filters.
+#[derive(Debug, Clone)]
+pub struct CreatedDesktop {
+    /// Zero-based index of the new desktop.
+    pub index: u32,
+    pub name: Option<Arc<str>>,
+}
+
+/// A window that was moved to another virtual desktop while applying filters.
+#[derive(Debug, Clone)]
+pub struct MovedWindow {
+    pub title: String,
+    pub process_name: Arc<str>,
+    /// Zero-based index of the desktop the window was moved to.
+    pub target: u32,
 }
 
+/// Apply filters to the currently open windows.
+///
+/// The `seen_windows` are updated with the currently open windows before the
+/// filters are checked and afterwards they remember which filters were
+/// successfully applied to which windows. If `only_unsettled` is `true` then
+/// windows that had already settled when the normal filters were last applied
+/// to them are left alone. Set `normal_filters` to `false` if only some of the
+/// user's filters are applied, since rechecks should still check windows
+/// against all of them.
 pub fn apply_filters(
-    filters_to_apply: Option<&[WindowFilter]>,
+    filters_to_apply: Option<&FilterMatcher>,
     stop_flashing: bool,
     stop_flashing_globally: bool,
-) {
-    let windows = WindowInfo::get_all();
-    let mut windows_to_prevent_flashing =
-        Vec::with_capacity(if stop_flashing || stop_flashing_globally {
-            windows.len()
-        } else {
-            0
-        });
-    for (ix, window) in windows.into_iter().enumerate() {
-        if stop_flashing_globally {
-            windows_to_prevent_flashing.push((
-                window.handle.as_hwnd(),
-                if let VirtualDesktopInfo::AtDesktop { desktop, .. } = window.virtual_desktop {
-                    Some(desktop)
-                } else {
-                    None
-                },
-            ))
+    exclusions: Arc<WindowExclusions>,
+    seen_windows: &mut Arc<SeenWindows>,
+    only_unsettled: bool,
+    normal_filters: bool,
+) -> ApplyFiltersResult {
+    let windows = window_info::get_all_window_info();
+    if filters_to_apply.is_some() {
+        // Also forgets windows that have been closed:
+        Arc::make_mut(seen_windows).observe_all(&windows, Instant::now());
+    }
+    let plan = plan_for_windows(
+        filters_to_apply,
+        windows,
+        stop_flashing,
+        stop_flashing_globally,
+        FilterContext::default()
+            .with_exclusions(exclusions)
+            .with_seen_windows(seen_windows.clone())
+            .with_only_unsettled(only_unsettled),
+    );
+    let mut result = execute_plan(&plan);
+    result.recheck_after = plan.recheck_after;
+    if filters_to_apply.is_some() {
+        let seen_windows = Arc::make_mut(seen_windows);
+        if normal_filters {
+            seen_windows.mark_applied();
         }
-        let Some(filter_list) = &filters_to_apply else {
-            continue;
-        };
-        let Some(action_info) = WindowFilter::find_first_action(filter_list, ix as i32, &window)
-        else {
-            continue;
-        };
+        for applied in &result.applied_filters {
+            seen_windows.mark_filter_applied(
+                applied.filter_index,
+                applied.window,
+                applied.process_id,
+            );
+        }
+    }
+    result
+}
 
-        if window.virtual_desktop.is_app_pinned() {
-            // Don't interact with process that have all of their windows pinned.
-            continue;
+/// Determine what [`apply_filters`] would do to the currently open windows
+/// without changing anything. Windows that match the `exclusions` are never
+/// affected.
+pub fn plan_filters(
+    filters_to_apply: Option<&FilterMatcher>,
+    stop_flashing: bool,
+    stop_flashing_globally: bool,
+    exclusions: Arc<WindowExclusions>,
+    seen_windows: Arc<SeenWindows>,
+) -> FilterPlan {
+    plan_for_windows(
+        filters_to_apply,
+        window_info::get_all_window_info(),
+        stop_flashing,
+        stop_flashing_globally,
+        FilterContext::default()
+            .with_exclusions(exclusions)
+            .with_seen_windows(seen_windows),
+    )
+}
+/// Plan for the given windows. Information about the virtual desktops is
+/// gathered into the `context` if there are filters to apply.
+fn plan_for_windows(
+    filters_to_apply: Option<&FilterMatcher>,
+    windows: Vec<WindowInfo>,
+    stop_flashing: bool,
+    stop_flashing_globally: bool,
+    context: FilterContext,
+) -> FilterPlan {
+    let context = if filters_to_apply.is_some() {
+        FilterContext {
+            exclusions: context.exclusions,
+            seen_windows: context.seen_windows,
+            only_unsettled: context.only_unsettled,
+            ..window_info::gather_filter_context()
         }
+    } else {
+        context
+    };
+    filter_plan::plan_filters(
+        filters_to_apply,
+        windows,
+        context,
+        stop_flashing,
+        stop_flashing_globally,
+    )
+}
 
-        let mut move_to_target_desktop = || {
-            let Ok(target_desktop_zero_based) = u32::try_from(action_info.target_desktop) else {
-                tracing::error!(info =? action_info, "Tried to target a desktop outside the range of u32");
-                return;
-            };
-            if let VirtualDesktopInfo::AtDesktop { index, .. } = window.virtual_desktop {
-                let target = vd::get_desktop(target_desktop_zero_based);
-                if stop_flashing_globally {
-                    windows_to_prevent_flashing.last_mut().unwrap().1 = Some(target);
-                } else if index == target_desktop_zero_based {
-                    // Already at wanted desktop
-                } else if stop_flashing {
-                    windows_to_prevent_flashing.push((window.handle.as_hwnd(), Some(target)));
-                } else if let Err(e) = vd::move_window_to_desktop(target, &window.handle.as_hwnd())
-                {
-                    tracing::warn!(error = ?e, "Failed to move window to target desktop");
+/// Preform the operations in a plan created by [`plan_filters`].
+pub fn execute_plan(plan: &FilterPlan) -> ApplyFiltersResult {
+    let executed = filter_plan::execute_plan(plan, &mut Win32WindowController);
+    let result = ApplyFiltersResult {
+        created_desktops: executed
+            .created_desktops
+            .into_iter()
+            .map(|(index, name)| CreatedDesktop { index, name })
+            .collect(),
+        notify_moved: executed
+            .notify_moved
+            .into_iter()
+            .map(|(window, target)| {
+                let window = &plan.windows[window];
+                MovedWindow {
+                    title: window.title.clone(),
+                    process_name: window.process_name.clone(),
+                    target,
                 }
-            }
-        };
-        let unpin_window = || {
-            if window.virtual_desktop.is_window_pinned() {
-                if let Err(e) = vd::unpin_window(window.handle.as_hwnd()) {
-                    tracing::warn!(error = ?e, "Failed to unpin window");
-                    return false;
-                }
-            }
-            true
-        };
-        let stop_flashing_without_move = |windows_to_prevent_flashing: &mut Vec<(_, _)>| {
-            if stop_flashing_globally {
-                windows_to_prevent_flashing.last_mut().unwrap().1 = None;
-            } else if stop_flashing {
-                windows_to_prevent_flashing.push((window.handle.as_hwnd(), None));
-            }
-        };
+            })
+            .collect(),
+        applied_filters: executed
+            .applied_filters
+            .into_iter()
+            .map(|(window, filter_index)| AppliedFilter {
+                filter_index,
+                window: plan.windows[window].handle,
+                process_id: plan.windows[window].process_id,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    if !result.created_desktops.is_empty() {
+        tracing::info!(
+            created_desktops = ?result.created_desktops,
+            "Created virtual desktops while applying filters"
+        );
+    }
+    result
+}
 
-        match action_info.action {
-            FilterAction::Move => move_to_target_desktop(),
-            FilterAction::UnpinAndMove => {
-                if unpin_window() {
-                    move_to_target_desktop();
-                }
-            }
-            FilterAction::Unpin => {
-                unpin_window();
-                stop_flashing_without_move(&mut windows_to_prevent_flashing);
-            }
-            FilterAction::Pin => {
-                if window.virtual_desktop.is_at_desktop() {
-                    if let Err(e) = vd::pin_window(window.handle.as_hwnd()) {
-                        tracing::warn!(error = ?e, "Failed to pin window");
-                    }
-                }
-                stop_flashing_without_move(&mut windows_to_prevent_flashing);
-            }
-            FilterAction::Nothing | FilterAction::Disabled => {}
+/// Sends results that should be shown to the user from the background thread
+/// to the UI thread.
+#[derive(Clone)]
+struct ResultSender {
+    sender: mpsc::Sender<ApplyFiltersResult>,
+    latest_notice_sender: Arc<Mutex<Option<nwg::NoticeSender>>>,
+}
+impl ResultSender {
+    fn send(&self, result: ApplyFiltersResult) {
+        if self.sender.send(result).is_err() {
+            return;
+        }
+        if let Some(sender) = *self.latest_notice_sender.lock().unwrap() {
+            sender.notice();
         }
     }
+}
 
-    if let Err(e) = vd::stop_flashing_windows_blocking(windows_to_prevent_flashing) {
-        tracing::error!(
-            error = e.to_string(),
-            globally = stop_flashing_globally,
-            "Failed to prevent windows from flashing"
-        );
+/// The user's normal filters that were most recently sent to the background
+/// thread. They are applied again when windows have settled.
+struct LatestFilters {
+    matcher: Arc<FilterMatcher>,
+    stop_flashing: bool,
+    exclusions: Arc<WindowExclusions>,
+}
+impl LatestFilters {
+    /// The shortest settle delay of any enabled filter, `None` if no filter
+    /// waits for windows to settle.
+    fn shortest_settle_delay(&self) -> Option<Duration> {
+        self.matcher
+            .filters()
+            .iter()
+            .filter(|filter| filter.action != FilterAction::Disabled && filter.settle_delay_ms != 0)
+            .map(WindowFilter::settle_delay)
+            .min()
     }
 }
 
@@ -133,18 +266,18 @@ struct ThreadInfo {
     sender: mpsc::Sender<BackgroundAction>,
 }
 impl ThreadInfo {
-    pub fn start() -> Self {
+    pub fn start(results: ResultSender) -> Self {
         let (tx, rx) = mpsc::channel::<BackgroundAction>();
         let join_handle = std::thread::Builder::new()
             .name("ApplyFiltersThread".to_owned())
-            .spawn(move || Self::background_work(rx))
+            .spawn(move || Self::background_work(rx, results))
             .expect("should be able to spawn thread for applying window filters/rules");
         Self {
             join_handle,
             sender: tx,
         }
     }
-    fn background_work(rx: mpsc::Receiver<BackgroundAction>) {
+    fn background_work(rx: mpsc::Receiver<BackgroundAction>, results: ResultSender) {
         if vd::has_loaded_dynamic_library_successfully() {
             // Old .dll files might not call `CoInitialize` and then not work,
             // so to be safe we make sure to do that:
@@ -155,21 +288,59 @@ impl ThreadInfo {
                 );
             }
         }
-        'outer: while let Ok(latest_action) = rx.recv() {
+        // Windows that filters were applied to, see `WindowFilter::only_first_time`:
+        let mut seen_windows = Arc::<SeenWindows>::default();
+        // Used to check windows again once they have settled, see
+        // `WindowFilter::settle_delay_ms`:
+        let mut latest_filters = None::<LatestFilters>;
+        let mut recheck_at = None::<Instant>;
+        'outer: loop {
+            let received = match recheck_at {
+                Some(at) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+                None => rx.recv().map_err(mpsc::RecvTimeoutError::from),
+            };
             let mut filters_to_apply = None;
+            let mut recheck_filters = None;
             let mut stop_flashing = false;
             let mut stop_flashing_globally = false;
+            let mut exclusions = Arc::default();
+            let mut recheck = false;
             let mut queue_action = |action| match action {
                 BackgroundAction::ApplyFilters {
                     filters,
+                    recheck_filters: normal,
                     stop_flashing_windows,
+                    exclusions: latest_exclusions,
                 } => {
                     filters_to_apply = Some(filters);
+                    recheck_filters = Some(normal);
                     stop_flashing |= stop_flashing_windows;
+                    exclusions = latest_exclusions;
+                }
+                BackgroundAction::StopFlashingWindows {
+                    exclusions: latest_exclusions,
+                } => {
+                    stop_flashing_globally = true;
+                    exclusions = latest_exclusions;
+                }
+                BackgroundAction::WindowChanged(handle) => {
+                    let Some(delay) = latest_filters
+                        .as_ref()
+                        .and_then(LatestFilters::shortest_settle_delay)
+                    else {
+                        return;
+                    };
+                    if observe_changed_window(Arc::make_mut(&mut seen_windows), handle) {
+                        let at = Instant::now() + delay;
+                        recheck_at = Some(recheck_at.map_or(at, |prev| prev.min(at)));
+                    }
                 }
-                BackgroundAction::StopFlashingWindows => stop_flashing_globally = true,
             };
-            queue_action(latest_action);
+            match received {
+                Ok(action) => queue_action(action),
+                Err(mpsc::RecvTimeoutError::Timeout) => recheck = true,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
             loop {
                 match rx.try_recv() {
                     // Only apply the latest filter list:
@@ -181,20 +352,93 @@ impl ThreadInfo {
                 }
             }
 
-            apply_filters(
+            // One-off applies, like bringing windows here, only contain some
+            // of the normal filters:
+            let normal_filters = match (&filters_to_apply, &recheck_filters) {
+                (Some(filters), Some(normal)) => Arc::ptr_eq(filters, normal),
+                _ => false,
+            };
+            if let Some(normal) = recheck_filters {
+                if let Some(latest) = &latest_filters {
+                    if !Arc::ptr_eq(&latest.matcher, &normal) {
+                        // Keep remembering which filters were applied to
+                        // windows even if the filter list was edited:
+                        Arc::make_mut(&mut seen_windows)
+                            .remap_filters(latest.matcher.filters(), normal.filters());
+                    }
+                }
+                latest_filters = Some(LatestFilters {
+                    matcher: normal,
+                    stop_flashing,
+                    exclusions: exclusions.clone(),
+                });
+            }
+
+            // Rechecks shouldn't undo changes to windows that had already
+            // settled:
+            let mut only_unsettled = false;
+            if recheck {
+                if filters_to_apply.is_none() && !stop_flashing_globally {
+                    recheck_at = None;
+                    if let Some(latest) = &latest_filters {
+                        filters_to_apply = Some(latest.matcher.clone());
+                        stop_flashing = latest.stop_flashing;
+                        exclusions = latest.exclusions.clone();
+                        only_unsettled = true;
+                    }
+                } else if normal_filters {
+                    recheck_at = None;
+                }
+                // Otherwise the recheck happens right after this one-off
+                // apply since `recheck_at` has already passed.
+            }
+            if filters_to_apply.is_none() && !stop_flashing_globally {
+                // Only changed windows that will be checked later:
+                continue;
+            }
+
+            let mut result = apply_filters(
                 filters_to_apply.as_deref(),
                 stop_flashing,
                 stop_flashing_globally,
+                exclusions,
+                &mut seen_windows,
+                only_unsettled,
+                only_unsettled || normal_filters,
             );
+            if only_unsettled || normal_filters {
+                recheck_at = result
+                    .recheck_after
+                    .take()
+                    .map(|delay| Instant::now() + delay);
+            }
+            if !result.notify_moved.is_empty() {
+                results.send(result);
+            }
         }
         tracing::info!("ApplyFilters thread exited since the original was dropped");
     }
 }
-#[derive(Default)]
-struct LazyThreadInfo(OnceCell<ThreadInfo>);
+/// Record a window whose title changed. Returns `true` if the window's title
+/// is different from when it was last observed.
+fn observe_changed_window(seen_windows: &mut SeenWindows, handle: WindowHandle) -> bool {
+    let hwnd = window_info::handle_to_hwnd(handle);
+    let (Ok(process_id), Ok(title)) = (
+        window_info::get_window_process_id(hwnd),
+        window_info::get_window_title(hwnd),
+    ) else {
+        return false;
+    };
+    seen_windows.observe(handle, process_id, &title, Instant::now())
+}
+
+struct LazyThreadInfo {
+    thread: OnceCell<ThreadInfo>,
+    results: ResultSender,
+}
 impl Drop for LazyThreadInfo {
     fn drop(&mut self) {
-        let Some(inner) = self.0.take() else {
+        let Some(inner) = self.thread.take() else {
             return;
         };
         // Notify background thread to exit:
@@ -207,39 +451,184 @@ impl Deref for LazyThreadInfo {
     type Target = ThreadInfo;
 
     fn deref(&self) -> &Self::Target {
-        self.0.get_or_init(ThreadInfo::start)
+        self.thread
+            .get_or_init(|| ThreadInfo::start(self.results.clone()))
+    }
+}
+
+/// Which of the user's filters to apply, see [`ApplyFilters::apply_filters`].
+#[derive(Debug, Clone, Copy)]
+pub enum FilterSelection<'a> {
+    /// All filters in enabled groups.
+    Enabled,
+    /// Only filters with the "Bring here" actions, see
+    /// [`WindowFilter::bring_here_filters`].
+    BringHere,
+    /// The filters in a group even if that group is disabled.
+    Group(&'a str),
+}
+
+/// Filters that are prepared on the UI thread whenever the settings change so
+/// that applying them doesn't need to prepare them again.
+struct PreparedFilters {
+    /// All filters in enabled groups. Filters in disabled groups are kept
+    /// with the [`FilterAction::Disabled`] action so that every filter has
+    /// the same index in every list that is applied.
+    enabled: Arc<FilterMatcher>,
+    bring_here: Arc<FilterMatcher>,
+}
+impl PreparedFilters {
+    fn new(settings: &UiSettings) -> Self {
+        let enabled = Arc::<[WindowFilter]>::from(FilterGroup::disable_filters_in_disabled_groups(
+            &settings.filter_groups,
+            &settings.filters,
+        ));
+        Self {
+            bring_here: Arc::new(FilterMatcher::new(
+                WindowFilter::bring_here_filters(&enabled).into(),
+            )),
+            enabled: Arc::new(FilterMatcher::new(enabled)),
+        }
     }
 }
 
 /// Apply filters on a background thread.
-#[derive(Default, NwgPartial)]
+#[derive(NwgPartial)]
 pub struct ApplyFilters {
+    tray: SystemTrayRef,
     background: LazyThreadInfo,
+    /// Prepared when filters are first applied and then kept up to date.
+    prepared: RefCell<Option<PreparedFilters>>,
+
+    results: mpsc::Receiver<ApplyFiltersResult>,
+    latest_notice_sender: Arc<Mutex<Option<nwg::NoticeSender>>>,
+    /// This notice will be triggered when the background thread has results
+    /// that should be shown to the user.
+    #[nwg_control]
+    #[nwg_events( OnNotice: [Self::on_background_notice] )]
+    background_notice: nwg::Notice,
+}
+impl Default for ApplyFilters {
+    fn default() -> Self {
+        let latest_notice_sender = Arc::new(Mutex::new(None::<nwg::NoticeSender>));
+        let (tx, rx) = mpsc::channel();
+        Self {
+            tray: Default::default(),
+            background: LazyThreadInfo {
+                thread: OnceCell::new(),
+                results: ResultSender {
+                    sender: tx,
+                    latest_notice_sender: latest_notice_sender.clone(),
+                },
+            },
+            prepared: RefCell::new(None),
+            results: rx,
+            latest_notice_sender,
+            background_notice: Default::default(),
+        }
+    }
 }
 impl DynamicUiHooks<SystemTray> for ApplyFilters {
     fn before_partial_build(
         &mut self,
-        _tray_ui: &Rc<SystemTray>,
+        tray: &Rc<SystemTray>,
         _should_build: &mut bool,
     ) -> Option<(nwg::ControlHandle, TypeId)> {
-        None
+        self.tray.set(tray);
+        Some((tray.root().window.handle, TypeId::of::<TrayRoot>()))
+    }
+    fn after_partial_build(&mut self, _tray: &Rc<SystemTray>) {
+        *self.latest_notice_sender.lock().unwrap() = Some(self.background_notice.sender());
+    }
+    fn before_rebuild(&mut self, _tray: &Rc<SystemTray>) {
+        self.background_notice = Default::default();
+    }
+}
+impl TrayPlugin for ApplyFilters {
+    fn on_desktop_event(&self, _tray_ui: &Rc<SystemTray>, event: &vd::DesktopEvent) {
+        let vd::DesktopEvent::WindowChanged(hwnd) = event else {
+            return;
+        };
+        // Changed windows only matter after filters have been applied:
+        if let Some(thread) = self.background.thread.get() {
+            let _ = thread
+                .sender
+                .send(BackgroundAction::WindowChanged(WindowHandle(
+                    hwnd.0 as isize,
+                )));
+        }
+    }
+    fn on_settings_changed(
+        &self,
+        _tray_ui: &Rc<SystemTray>,
+        prev: &Arc<UiSettings>,
+        new: &Arc<UiSettings>,
+    ) {
+        let mut prepared = self.prepared.borrow_mut();
+        if prepared.is_none() {
+            // Filters haven't been applied yet:
+            return;
+        }
+        if prev.filters == new.filters && prev.filter_groups == new.filter_groups {
+            return;
+        }
+        *prepared = Some(PreparedFilters::new(new));
     }
 }
-impl TrayPlugin for ApplyFilters {}
 impl ApplyFilters {
-    pub fn apply_filters(&self, filters: Arc<[WindowFilter]>, stop_flashing_windows: bool) {
+    fn on_background_notice(&self) {
+        let Some(tray) = self.tray.get() else {
+            return;
+        };
+        let moved = self
+            .results
+            .try_iter()
+            .flat_map(|result| result.notify_moved)
+            .collect::<Vec<_>>();
+        let text = moved
+            .iter()
+            .map(|window| {
+                format!(
+                    "Moved {:?} ({}) to desktop {}",
+                    window.title,
+                    window.process_name,
+                    window.target.saturating_add(1)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.is_empty() {
+            tray.show_notification("Virtual Desktop Manager", &text);
+        }
+    }
+
+    /// Apply some of the user's filters to all windows. Windows are later
+    /// checked against all enabled filters once they have settled, see
+    /// [`WindowFilter::settle_delay_ms`].
+    pub fn apply_filters(&self, selection: FilterSelection<'_>, settings: &UiSettings) {
+        let mut prepared = self.prepared.borrow_mut();
+        let prepared = prepared.get_or_insert_with(|| PreparedFilters::new(settings));
+        let filters = match selection {
+            FilterSelection::Enabled => prepared.enabled.clone(),
+            FilterSelection::BringHere => prepared.bring_here.clone(),
+            FilterSelection::Group(group) => Arc::new(FilterMatcher::new(
+                FilterGroup::disable_filters_outside_group(&settings.filters, group).into(),
+            )),
+        };
         self.background
             .sender
             .send(BackgroundAction::ApplyFilters {
                 filters,
-                stop_flashing_windows,
+                recheck_filters: prepared.enabled.clone(),
+                stop_flashing_windows: settings.stop_flashing_windows_after_applying_filter,
+                exclusions: settings.window_exclusions.clone(),
             })
             .expect("send work to ApplyFilter thread");
     }
-    pub fn stop_all_flashing_windows(&self) {
+    pub fn stop_all_flashing_windows(&self, exclusions: Arc<WindowExclusions>) {
         self.background
             .sender
-            .send(BackgroundAction::StopFlashingWindows)
+            .send(BackgroundAction::StopFlashingWindows { exclusions })
             .expect("send work to ApplyFilter thread");
     }
 }
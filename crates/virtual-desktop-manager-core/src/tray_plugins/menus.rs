@@ -6,6 +6,7 @@ use crate::{
     settings::{AutoStart, QuickSwitchMenu, TrayIconType, UiSettings},
     tray::{MenuKeyPressEffect, MenuPosition, SystemTray, SystemTrayRef, TrayPlugin, TrayRoot},
     vd,
+    window_filter::FilterGroup,
 };
 use std::{
     any::TypeId,
@@ -886,6 +887,14 @@ pub struct BottomMenuItems {
     #[nwg_events(OnMenuItemSelected: [Self::apply_filters])]
     tray_apply_filters: nwg::MenuItem,
 
+    #[nwg_control(text: "Bring Windows &Here")]
+    #[nwg_events(OnMenuItemSelected: [Self::bring_windows_here])]
+    tray_bring_windows_here: nwg::MenuItem,
+
+    /// Parent of the [`FilterGroupsMenu`] items.
+    #[nwg_control(text: "Filter &Groups")]
+    tray_filter_groups_menu: nwg::Menu,
+
     #[nwg_control]
     tray_sep2: nwg::MenuSeparator,
 
@@ -895,7 +904,9 @@ pub struct BottomMenuItems {
 }
 /// Handle menu clicks.
 impl BottomMenuItems {
-    forward_to_dynamic_ui!(tray_ui => apply_filters, stop_flashing_windows, exit);
+    forward_to_dynamic_ui!(
+        tray_ui => apply_filters, bring_windows_here, stop_flashing_windows, exit
+    );
 
     fn open_filter_config(&self) {
         let Some(tray_ui) = self.tray_ui.get() else {
@@ -915,3 +926,122 @@ impl DynamicUiHooks<SystemTray> for BottomMenuItems {
     }
 }
 impl TrayPlugin for BottomMenuItems {}
+
+/// Menu items to apply a single group of filters or to enable/disable groups.
+/// These are placed inside a submenu of [`BottomMenuItems`].
+#[derive(Default)]
+pub struct FilterGroupsMenu {
+    tray_ui: SystemTrayRef,
+
+    /// Update right before UI build, so we can use this to track if we need to
+    /// rebuild.
+    groups: Vec<FilterGroup>,
+
+    /// Shown instead of the other items if there are no groups.
+    tray_no_groups: Option<nwg::MenuItem>,
+    /// One menu item per group that applies that group's filters.
+    tray_apply_group: Vec<nwg::MenuItem>,
+    tray_sep: Option<nwg::MenuSeparator>,
+    /// One menu item per group that is checked when the group is enabled.
+    tray_enable_group: Vec<nwg::MenuItem>,
+}
+impl nwg::PartialUi for FilterGroupsMenu {
+    fn build_partial<W: Into<nwg::ControlHandle>>(
+        data: &mut Self,
+        parent: Option<W>,
+    ) -> Result<(), nwg::NwgError> {
+        let parent = parent.map(Into::into).ok_or_else(|| {
+            nwg::NwgError::MenuCreationError("No parent defined for FilterGroupsMenu".to_string())
+        })?;
+        if data.groups.is_empty() {
+            let mut item = Default::default();
+            nwg::MenuItem::builder()
+                .text("No filter groups")
+                .disabled(true)
+                .parent(parent)
+                .build(&mut item)?;
+            data.tray_no_groups = Some(item);
+            return Ok(());
+        }
+        for group in &data.groups {
+            let mut item = Default::default();
+            nwg::MenuItem::builder()
+                .text(&format!("Apply \"{}\"", group.name))
+                .parent(parent)
+                .build(&mut item)?;
+            data.tray_apply_group.push(item);
+        }
+        let mut sep = Default::default();
+        MenuSeparator::builder().parent(parent).build(&mut sep)?;
+        data.tray_sep = Some(sep);
+        for group in &data.groups {
+            let mut item = Default::default();
+            nwg::MenuItem::builder()
+                .text(&format!("Enable \"{}\"", group.name))
+                .check(group.enabled)
+                .parent(parent)
+                .build(&mut item)?;
+            data.tray_enable_group.push(item);
+        }
+        Ok(())
+    }
+    fn process_event(
+        &self,
+        evt: nwg::Event,
+        _evt_data: &nwg::EventData,
+        handle: nwg::ControlHandle,
+    ) {
+        let nwg::Event::OnMenuItemSelected = evt else {
+            return;
+        };
+        let Some(tray_ui) = self.tray_ui.get() else {
+            return;
+        };
+        if let Some(ix) = self
+            .tray_apply_group
+            .iter()
+            .position(|item| item.handle == handle)
+        {
+            tray_ui.apply_filter_group(&self.groups[ix].name);
+        } else if let Some(ix) = self
+            .tray_enable_group
+            .iter()
+            .position(|item| item.handle == handle)
+        {
+            let group = &self.groups[ix];
+            tray_ui.settings().update(|prev| UiSettings {
+                filter_groups: FilterGroup::set_enabled(
+                    &prev.filter_groups,
+                    &group.name,
+                    !group.enabled,
+                ),
+                ..prev.clone()
+            });
+        }
+    }
+}
+impl DynamicUiHooks<SystemTray> for FilterGroupsMenu {
+    fn before_partial_build(
+        &mut self,
+        tray_ui: &Rc<SystemTray>,
+        should_build: &mut bool,
+    ) -> Option<(nwg::ControlHandle, TypeId)> {
+        let Some(parent) = tray_ui.dynamic_ui.get_ui::<BottomMenuItems>() else {
+            tracing::warn!("No BottomMenuItems so filter group menu items won't be shown");
+            *should_build = false;
+            return None;
+        };
+        let settings = tray_ui.settings().get();
+        self.groups = FilterGroup::all_groups(&settings.filter_groups, &settings.filters);
+        self.tray_ui.set(tray_ui);
+        Some((
+            parent.tray_filter_groups_menu.handle,
+            TypeId::of::<BottomMenuItems>(),
+        ))
+    }
+    fn need_rebuild(&self, tray_ui: &Rc<SystemTray>) -> bool {
+        let settings = tray_ui.settings().get();
+        self.groups != FilterGroup::all_groups(&settings.filter_groups, &settings.filters)
+    }
+}
+impl TrayPlugin for FilterGroupsMenu {}
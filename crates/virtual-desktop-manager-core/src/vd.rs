@@ -292,6 +292,38 @@ impl Desktop {
             }
         }
     }
+    pub fn set_name(&self, name: &str) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "winvd_static")]
+            Self::Static(d) => Ok(d.set_name(name)?),
+            _ => {
+                #[cfg(feature = "winvd_dynamic")]
+                if let Some(Ok(symbols)) = dynamic::get_loaded_symbols() {
+                    let desktop_number = self.get_index()? as i32;
+                    let name =
+                        std::ffi::CString::new(name).map_err(|_| Error::DesktopNameWithNul)?;
+                    let in_name_ptr = name.as_ptr();
+                    let res = unsafe { symbols.SetDesktopName(desktop_number, in_name_ptr)? };
+                    if res == 0 {
+                        // winvd::Desktop::set_name returned an error
+                        return Err(Error::DynamicCall(dynamic::DynamicError::SetDesktopName {
+                            desktop_number,
+                            in_name_ptr,
+                        }));
+                    }
+                    return Ok(());
+                }
+                #[cfg(feature = "winvd_static")]
+                {
+                    return Ok(winvd::Desktop::from(*self).set_name(name)?);
+                }
+                #[allow(unreachable_code)]
+                {
+                    Err(no_dynamic_library_error())
+                }
+            }
+        }
+    }
 }
 #[cfg(feature = "winvd_static")]
 impl From<winvd::Desktop> for Desktop {
@@ -409,6 +441,8 @@ pub enum Error {
     StaticCall(winvd::Error),
     NonUtf8DesktopName(String),
     DesktopNameWithoutNul,
+    /// Desktop names can't contain nul characters.
+    DesktopNameWithNul,
 }
 #[cfg(feature = "winvd_static")]
 impl From<winvd::Error> for Error {
@@ -436,6 +470,9 @@ impl fmt::Display for Error {
             ),
             Self::NonUtf8DesktopName(name) => write!(f, "Non-UTF8 desktop name: {name}"),
             Self::DesktopNameWithoutNul => write!(f, "Invalid virtual desktop name"),
+            Self::DesktopNameWithNul => {
+                write!(f, "Virtual desktop names can't contain nul characters")
+            }
         }
     }
 }
@@ -613,6 +650,44 @@ pub fn unpin_window(hwnd: HWND) -> Result<()> {
     Err(no_dynamic_library_error())
 }
 
+/// Wrapper around [`winvd::pin_app`] (but prefers dynamic loaded library if
+/// it exists).
+pub fn pin_app(hwnd: HWND) -> Result<()> {
+    #[cfg(feature = "winvd_dynamic")]
+    {
+        if let Some(Ok(symbols)) = dynamic::get_loaded_symbols() {
+            symbols.PinApp(hwnd)?;
+            return Ok(());
+        }
+    }
+    #[cfg(feature = "winvd_static")]
+    {
+        winvd::pin_app(hwnd)?;
+        return Ok(());
+    }
+    #[allow(unreachable_code)]
+    Err(no_dynamic_library_error())
+}
+
+/// Wrapper around [`winvd::unpin_app`] (but prefers dynamic loaded library if
+/// it exists).
+pub fn unpin_app(hwnd: HWND) -> Result<()> {
+    #[cfg(feature = "winvd_dynamic")]
+    {
+        if let Some(Ok(symbols)) = dynamic::get_loaded_symbols() {
+            symbols.UnPinApp(hwnd)?;
+            return Ok(());
+        }
+    }
+    #[cfg(feature = "winvd_static")]
+    {
+        winvd::unpin_app(hwnd)?;
+        return Ok(());
+    }
+    #[allow(unreachable_code)]
+    Err(no_dynamic_library_error())
+}
+
 /// Wrapper around [`winvd::switch_desktop`] (but prefers dynamic loaded
 /// library if it exists).
 pub fn switch_desktop(desktop: Desktop) -> Result<()> {
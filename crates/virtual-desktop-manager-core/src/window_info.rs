@@ -1,19 +1,35 @@
 //! Helper methods to get window information.
 
-use std::{collections::HashMap, fmt, ops::ControlFlow, sync::Arc};
+use std::{collections::HashMap, ops::ControlFlow, sync::Arc};
 use windows::{
     core::{Error, PWSTR},
     Win32::{
-        Foundation::{CloseHandle, HANDLE, HWND},
-        System::Threading::{
-            OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
-            PROCESS_QUERY_LIMITED_INFORMATION,
+        Foundation::{CloseHandle, HANDLE, HWND, LPARAM, WPARAM},
+        System::{
+            SystemInformation::GetLocalTime,
+            Threading::{
+                OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
+                PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+        },
+        UI::WindowsAndMessaging::{
+            GetClassNameW, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId,
+            PostMessageW, SetForegroundWindow, ShowWindowAsync, SW_MAXIMIZE, SW_MINIMIZE,
+            SW_RESTORE, WM_CLOSE,
         },
-        UI::WindowsAndMessaging::{GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId},
     },
 };
 
-use crate::{nwg_ext::enum_child_windows, vd};
+use crate::{
+    filter_plan::{WindowCommand, WindowController},
+    nwg_ext::enum_child_windows,
+    vd,
+    window_filter::{FilterContext, LocalTime, TimeOfDay, Weekday},
+};
+
+pub use virtual_desktop_manager_filters::window_info::{
+    VirtualDesktopInfo, WindowHandle, WindowInfo,
+};
 
 /// Simple wrapper around [`enum_child_windows`].
 pub fn all_windows() -> Vec<HWND> {
@@ -46,6 +62,23 @@ pub fn get_window_title(window: HWND) -> Result<String, Error> {
     }
 }
 
+/// Get the name of the class that a window belongs to.
+///
+/// # References
+///
+/// - [GetClassNameW function (winuser.h) - Win32 apps | Microsoft
+///   Learn](https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getclassnamew)
+pub fn get_window_class_name(window: HWND) -> Result<String, Error> {
+    // The maximum length for a class name is 256 characters:
+    let mut class_name: Vec<u16> = vec![0; 257];
+    let len = unsafe { GetClassNameW(window, &mut class_name) };
+    if len != 0 {
+        Ok(String::from_utf16(&class_name[0..(len as usize)])?)
+    } else {
+        Err(Error::from_win32())
+    }
+}
+
 /// Get the identifier of the process that created a specified window.
 ///
 /// # References
@@ -102,74 +135,70 @@ pub fn get_process_full_name(process_id: u32) -> Result<String, Error> {
 }
 
 /// Get the name of a process.
-#[allow(clippy::assigning_clones)]
 pub fn get_process_name(process_id: u32) -> Result<String, Error> {
-    let mut exe_path = get_process_full_name(process_id)?;
-    if let Some(slash) = exe_path.rfind(['\\', '/']) {
-        exe_path = exe_path[slash + 1..].to_owned();
-    }
-    if exe_path.ends_with(".exe") {
-        exe_path.truncate(exe_path.len() - 4);
-    }
-    Ok(exe_path)
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum VirtualDesktopInfo {
-    WindowPinned,
-    AppPinned,
-    AtDesktop {
-        /// GUID identifier for the virtual desktop.
-        desktop: vd::Desktop,
-        // Zero-based index for the virtual desktop when the info was gathered
-        // (it might have been moved after that).
-        index: u32,
-    },
+    Ok(process_name_from_path(&get_process_full_name(process_id)?).to_owned())
 }
-impl fmt::Display for VirtualDesktopInfo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::WindowPinned => write!(f, "Pinned Window"),
-            Self::AppPinned => write!(f, "Pinned App"),
-            Self::AtDesktop { index, .. } => fmt::Display::fmt(&(index + 1), f),
-        }
+
+/// Get the name of a process from the full path to its executable, see
+/// [`get_process_full_name`].
+pub fn process_name_from_path(exe_path: &str) -> &str {
+    let mut name = exe_path;
+    if let Some(slash) = name.rfind(['\\', '/']) {
+        name = &name[slash + 1..];
     }
+    name.strip_suffix(".exe").unwrap_or(name)
+}
+
+/// Get the names of all virtual desktops, ordered by their index.
+///
+/// Note: desktop names are only supported on Windows 11.
+pub fn get_desktop_names() -> vd::Result<Vec<Arc<str>>> {
+    vd::get_desktops()?
+        .into_iter()
+        .map(|desktop| Ok(Arc::from(desktop.get_name()?)))
+        .collect()
 }
-impl VirtualDesktopInfo {
-    pub fn new(window: HWND) -> vd::Result<Self> {
-        if vd::is_pinned_app(window)? {
-            Ok(Self::AppPinned)
-        } else if vd::is_pinned_window(window)? {
-            Ok(Self::WindowPinned)
-        } else {
-            let desktop = vd::get_window_desktop(window)?;
-            let index = desktop.get_index()?;
-            Ok(Self::AtDesktop { desktop, index })
-        }
-    }
 
-    /// Returns `true` if the virtual desktop info is [`WindowPinned`].
-    ///
-    /// [`WindowPinned`]: VirtualDesktopInfo::WindowPinned
-    #[must_use]
-    pub fn is_window_pinned(&self) -> bool {
-        matches!(self, Self::WindowPinned)
+/// Get the current time in the local time zone.
+pub fn get_local_time() -> LocalTime {
+    let time = unsafe { GetLocalTime() };
+    LocalTime {
+        weekday: Weekday::from_days_since_sunday(time.wDayOfWeek),
+        time: TimeOfDay::new(time.wHour as u8, time.wMinute as u8).unwrap_or_default(),
     }
+}
 
-    /// Returns `true` if the virtual desktop info is [`AppPinned`].
-    ///
-    /// [`AppPinned`]: VirtualDesktopInfo::AppPinned
-    #[must_use]
-    pub fn is_app_pinned(&self) -> bool {
-        matches!(self, Self::AppPinned)
+/// Get information about the filters' view of the current virtual desktops.
+pub fn gather_filter_context() -> FilterContext {
+    let desktop_names = get_desktop_names().unwrap_or_else(|e| {
+        tracing::debug!(error = %e, "Failed to get virtual desktop names for filters");
+        Vec::new()
+    });
+    let current_desktop = vd::get_current_desktop()
+        .and_then(|desktop| desktop.get_index())
+        .map_err(|e| tracing::warn!(error = %e, "Failed to get current virtual desktop"))
+        .ok();
+    let desktop_count = vd::get_desktop_count()
+        .map_err(|e| tracing::warn!(error = %e, "Failed to get virtual desktop count"))
+        .ok();
+    FilterContext {
+        desktop_names,
+        current_desktop,
+        desktop_count,
+        local_time: Some(get_local_time()),
+        ..FilterContext::default()
     }
+}
 
-    /// Returns `true` if the virtual desktop info is [`AtDesktop`].
-    ///
-    /// [`AtDesktop`]: VirtualDesktopInfo::AtDesktop
-    #[must_use]
-    pub fn is_at_desktop(&self) -> bool {
-        matches!(self, Self::AtDesktop { .. })
+/// Get the virtual desktop that a window is at.
+pub fn get_virtual_desktop_info(window: HWND) -> vd::Result<VirtualDesktopInfo> {
+    if vd::is_pinned_app(window)? {
+        Ok(VirtualDesktopInfo::AppPinned)
+    } else if vd::is_pinned_window(window)? {
+        Ok(VirtualDesktopInfo::WindowPinned)
+    } else {
+        let index = vd::get_window_desktop(window)?.get_index()?;
+        Ok(VirtualDesktopInfo::AtDesktop { index })
     }
 }
 
@@ -181,59 +210,135 @@ pub enum GetAllError {
     VirtualDesktop(vd::Error),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct WindowHandle(pub isize);
-impl WindowHandle {
-    pub fn as_hwnd(self) -> HWND {
-        HWND(self.0 as *mut _)
+pub fn handle_to_hwnd(handle: WindowHandle) -> HWND {
+    HWND(handle.0 as *mut _)
+}
+
+/// Changes real windows using the Win32 API and virtual desktops using the
+/// [`vd`] module.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Win32WindowController;
+impl WindowController for Win32WindowController {
+    type Error = Box<dyn std::error::Error>;
+
+    fn run_command(
+        &mut self,
+        window: &WindowInfo,
+        command: WindowCommand,
+    ) -> Result<(), Self::Error> {
+        let hwnd = handle_to_hwnd(window.handle);
+        // The async version is used since windows of other processes might be
+        // unresponsive:
+        let show = |cmd| unsafe { ShowWindowAsync(hwnd, cmd) }.ok();
+        let result = match command {
+            WindowCommand::Minimize => show(SW_MINIMIZE),
+            WindowCommand::Restore => show(SW_RESTORE),
+            WindowCommand::Maximize => show(SW_MAXIMIZE),
+            WindowCommand::Close => unsafe { PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0)) },
+            // Can fail if Windows doesn't allow this program to steal focus:
+            WindowCommand::Focus => unsafe { SetForegroundWindow(hwnd) }.ok(),
+        };
+        result.map_err(Into::into)
+    }
+    fn create_desktop(&mut self, index: u32, name: Option<&str>) -> Result<(), Self::Error> {
+        let desktop = vd::create_desktop()?;
+        match desktop.get_index() {
+            Ok(actual) if actual != index => tracing::warn!(
+                expected = index,
+                actual,
+                "Created desktop got an unexpected index"
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = ?e, "Failed to get index of created desktop"),
+        }
+        if let Some(name) = name {
+            if let Err(e) = desktop.set_name(name) {
+                tracing::warn!(error = ?e, ?name, "Failed to set name of created desktop");
+            }
+        }
+        Ok(())
+    }
+    fn move_to_desktop(&mut self, window: &WindowInfo, desktop: u32) -> Result<(), Self::Error> {
+        vd::move_window_to_desktop(vd::get_desktop(desktop), &handle_to_hwnd(window.handle))?;
+        Ok(())
+    }
+    fn pin_window(&mut self, window: &WindowInfo) -> Result<(), Self::Error> {
+        vd::pin_window(handle_to_hwnd(window.handle))?;
+        Ok(())
+    }
+    fn unpin_window(&mut self, window: &WindowInfo) -> Result<(), Self::Error> {
+        vd::unpin_window(handle_to_hwnd(window.handle))?;
+        Ok(())
+    }
+    fn pin_app(&mut self, window: &WindowInfo) -> Result<(), Self::Error> {
+        vd::pin_app(handle_to_hwnd(window.handle))?;
+        Ok(())
+    }
+    fn unpin_app(&mut self, window: &WindowInfo) -> Result<(), Self::Error> {
+        vd::unpin_app(handle_to_hwnd(window.handle))?;
+        Ok(())
+    }
+    fn stop_flashing(&mut self, windows: &[(&WindowInfo, Option<u32>)]) -> Result<(), Self::Error> {
+        vd::stop_flashing_windows_blocking(
+            windows
+                .iter()
+                .map(|&(window, target)| {
+                    (handle_to_hwnd(window.handle), target.map(vd::get_desktop))
+                })
+                .collect(),
+        )
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct WindowInfo {
-    pub handle: WindowHandle,
-    pub title: String,
-    pub process_id: u32,
-    pub process_name: Arc<str>,
-    pub virtual_desktop: VirtualDesktopInfo,
+/// Get info about all windows, skipping windows where that failed.
+pub fn get_all_window_info() -> Vec<WindowInfo> {
+    try_get_all_window_info()
+        .filter_map(|res| match res {
+            Ok(info) => Some(info),
+            Err(e) => {
+                tracing::trace!("Failed to get window info: {:?}", e);
+                None
+            }
+        })
+        .collect()
 }
-impl WindowInfo {
-    pub fn get_all() -> Vec<WindowInfo> {
-        Self::try_get_all()
-            .filter_map(|res| match res {
-                Ok(info) => Some(info),
-                Err(e) => {
-                    tracing::trace!("Failed to get window info: {:?}", e);
-                    None
-                }
+pub fn try_get_all_window_info() -> impl Iterator<Item = Result<WindowInfo, GetAllError>> {
+    // Executable path and process name for each process id:
+    let mut process_names: HashMap<u32, (Arc<str>, Arc<str>)> = HashMap::new();
+    all_windows()
+        .into_iter()
+        .map(move |handle| -> Result<WindowInfo, GetAllError> {
+            let virtual_desktop =
+                get_virtual_desktop_info(handle).map_err(GetAllError::VirtualDesktop)?;
+            let title = get_window_title(handle).map_err(GetAllError::Title)?;
+            // Filters can still match other properties if the class name is
+            // unavailable:
+            let class_name = get_window_class_name(handle).unwrap_or_else(|e| {
+                tracing::warn!(error = %e, window = ?handle, "Failed to get window class name");
+                String::new()
+            });
+            let process_id = get_window_process_id(handle).map_err(GetAllError::ProcessId)?;
+            let (executable_path, process_name) = if let Some(names) =
+                process_names.get(&process_id)
+            {
+                names.clone()
+            } else {
+                let path = get_process_full_name(process_id).map_err(GetAllError::ProcessName)?;
+                let names = (
+                    Arc::<str>::from(path.as_str()),
+                    Arc::<str>::from(process_name_from_path(&path)),
+                );
+                process_names.insert(process_id, names.clone());
+                names
+            };
+            Ok(WindowInfo {
+                handle: WindowHandle(handle.0 as isize),
+                title,
+                class_name,
+                process_id,
+                process_name,
+                executable_path,
+                virtual_desktop,
             })
-            .collect()
-    }
-    pub fn try_get_all() -> impl Iterator<Item = Result<WindowInfo, GetAllError>> {
-        let mut process_names: HashMap<u32, Arc<str>> = HashMap::new();
-        all_windows()
-            .into_iter()
-            .map(move |handle| -> Result<WindowInfo, GetAllError> {
-                let virtual_desktop =
-                    VirtualDesktopInfo::new(handle).map_err(GetAllError::VirtualDesktop)?;
-                let title = get_window_title(handle).map_err(GetAllError::Title)?;
-                let process_id = get_window_process_id(handle).map_err(GetAllError::ProcessId)?;
-                let process_name = if let Some(name) = process_names.get(&process_id) {
-                    name.clone()
-                } else {
-                    let name = Arc::<str>::from(
-                        get_process_name(process_id).map_err(GetAllError::ProcessName)?,
-                    );
-                    process_names.insert(process_id, name.clone());
-                    name
-                };
-                Ok(WindowInfo {
-                    handle: WindowHandle(handle.0 as isize),
-                    title,
-                    process_id,
-                    process_name,
-                    virtual_desktop,
-                })
-            })
-    }
+        })
 }
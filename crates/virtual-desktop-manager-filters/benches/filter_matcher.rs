@@ -0,0 +1,206 @@
+//! Compare [`FilterMatcher`] to checking every filter for every window using
+//! synthetic windows and filters. That both ways find the same filters is
+//! tested in the `filter_matcher` module.
+//!
+//! Run with `cargo bench -p virtual-desktop-manager-filters --bench filter_matcher`.
+
+use std::{
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use virtual_desktop_manager_filters::{
+    filter_matcher::FilterMatcher,
+    window_filter::{
+        FilterAction, FilterContext, IntegerRange, TextMatchMode, TextMatchOptions, TextPattern,
+        WindowFilter,
+    },
+    window_info::{VirtualDesktopInfo, WindowInfo},
+};
+
+/// Small deterministic random number generator (xorshift) so that every run
+/// uses the same windows and filters.
+struct Rng(u64);
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+}
+
+const APPS: &[&str] = &[
+    "firefox",
+    "Code",
+    "explorer",
+    "notepad",
+    "Teams",
+    "slack",
+    "WINWORD",
+    "EXCEL",
+    "chrome",
+    "Spotify",
+    "steam",
+    "obs64",
+    "cmd",
+    "WindowsTerminal",
+    "Discord",
+];
+const DOCUMENTS: &[&str] = &[
+    "Inbox",
+    "Report",
+    "Budget 2026",
+    "notes.txt",
+    "README.md",
+    "Meeting",
+    "Ärende",
+    "日本語",
+    "New Tab",
+    "Settings",
+];
+
+/// Process names that filters and windows are picked from. Large rule sets
+/// usually target many different programs, so the pool grows with the number
+/// of filters.
+fn app_names(count: usize) -> Vec<String> {
+    (0..count.max(APPS.len()))
+        .map(|ix| match ix / APPS.len() {
+            0 => APPS[ix].to_owned(),
+            n => format!("{}{n}", APPS[ix % APPS.len()]),
+        })
+        .collect()
+}
+
+fn synthetic_windows(rng: &mut Rng, apps: &[String], count: usize) -> Vec<WindowInfo> {
+    (0..count)
+        .map(|ix| {
+            let app = &apps[rng.below(apps.len())];
+            let document = DOCUMENTS[rng.below(DOCUMENTS.len())];
+            let index = rng.below(6) as u32;
+            let virtual_desktop = match rng.below(10) {
+                0 => VirtualDesktopInfo::WindowPinned,
+                1 => VirtualDesktopInfo::AppPinned,
+                _ => VirtualDesktopInfo::AtDesktop { index },
+            };
+            WindowInfo::for_test(
+                ix as isize + 1,
+                app,
+                &format!("{document} - {app}"),
+                virtual_desktop,
+            )
+        })
+        .collect()
+}
+
+fn synthetic_pattern(rng: &mut Rng, apps: &[String]) -> TextPattern {
+    let app = &apps[rng.below(apps.len())];
+    let document = DOCUMENTS[rng.below(DOCUMENTS.len())];
+    let ignore_case = TextMatchOptions {
+        ignore_case: true,
+        ..Default::default()
+    };
+    match rng.below(8) {
+        0 => TextPattern::new(Arc::from(format!("{document}\n"))),
+        1 => TextPattern::new(Arc::from(format!("\n - {app}"))),
+        2 => TextPattern::new(Arc::from(format!("{document}\n{app}\n"))),
+        3 => TextPattern::new(Arc::from(format!("\n{document}\n"))),
+        4 => TextPattern::new(Arc::from(format!("{document} - {app}"))),
+        5 => TextPattern::new(Arc::from(format!("{document} - {app}").to_uppercase()))
+            .with_mode(TextMatchMode::Exact)
+            .with_options(ignore_case),
+        6 => TextPattern::new(Arc::from(format!("^{document}.*{app}$")))
+            .with_mode(TextMatchMode::Regex),
+        _ => TextPattern::new(Arc::from(format!("{document} - \n"))).with_options(ignore_case),
+    }
+}
+
+fn synthetic_filters(rng: &mut Rng, apps: &[String], count: usize) -> Vec<WindowFilter> {
+    (0..count)
+        .map(|_| {
+            // Filters that match every process are rare:
+            let process_name = match rng.below(100) {
+                0 => TextPattern::default(),
+                1..=30 => TextPattern::new(Arc::from(apps[rng.below(apps.len())].as_str()))
+                    .with_mode(TextMatchMode::Exact)
+                    .with_options(TextMatchOptions {
+                        ignore_case: true,
+                        ..Default::default()
+                    }),
+                _ => TextPattern::new(Arc::from(apps[rng.below(apps.len())].as_str())),
+            };
+            let window_title = if !process_name.is_match_all() && rng.below(3) == 0 {
+                TextPattern::default()
+            } else {
+                synthetic_pattern(rng, apps)
+            };
+            WindowFilter {
+                desktop_index: if rng.below(4) == 0 {
+                    IntegerRange {
+                        lower_bound: Some(rng.below(3) as i64),
+                        upper_bound: Some(rng.below(6) as i64),
+                    }
+                } else {
+                    IntegerRange::default()
+                },
+                window_title,
+                process_name,
+                action: [
+                    FilterAction::Move,
+                    FilterAction::Pin,
+                    FilterAction::Minimize,
+                    FilterAction::Nothing,
+                    FilterAction::Disabled,
+                ][rng.below(5)],
+                target_desktop: rng.below(6) as i64,
+                continue_after_match: rng.below(4) == 0,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Time how long it takes to find the actions for all windows.
+fn measure(iterations: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let context = FilterContext::default();
+    for (window_count, filter_count) in [(50, 10), (200, 100), (500, 500), (1000, 2000)] {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d ^ (window_count * filter_count) as u64);
+        let apps = app_names(filter_count / 2);
+        let windows = synthetic_windows(&mut rng, &apps, window_count);
+        let filters = Arc::<[WindowFilter]>::from(synthetic_filters(&mut rng, &apps, filter_count));
+
+        let start = Instant::now();
+        let matcher = FilterMatcher::new(filters.clone());
+        let build_time = start.elapsed();
+
+        let iterations = 20;
+        let naive_time = measure(iterations, || {
+            for (ix, window) in windows.iter().enumerate() {
+                black_box(WindowFilter::find_actions(
+                    &filters, ix as i32, window, &context,
+                ));
+            }
+        });
+        let matcher_time = measure(iterations, || {
+            for (ix, window) in windows.iter().enumerate() {
+                black_box(matcher.find_actions(ix as i32, window, &context));
+            }
+        });
+        println!(
+            "{window_count} windows, {filter_count} filters: \
+            naive {naive_time:?}, matcher {matcher_time:?} (built in {build_time:?})"
+        );
+    }
+}
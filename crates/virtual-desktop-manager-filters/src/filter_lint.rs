@@ -0,0 +1,515 @@
+//! Find filters that can never do anything or that are likely mistakes, see
+//! [`lint_filters`] and [`lint_exclusions`].
+
+use std::{fmt, sync::Arc};
+
+use crate::window_filter::{
+    DesktopTarget, FilterAction, FilterCondition, FilterGroup, IntegerRange, IntegerSet,
+    MissingDesktop, TextPattern, WindowExclusions, WindowFilter,
+};
+
+/// A problem with a filter that was found by [`lint_filters`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterWarning {
+    /// Zero-based index of the filter that has the problem.
+    pub filter_index: usize,
+    pub kind: FilterWarningKind,
+}
+/// Displays the filter index using one-based indexing.
+impl fmt::Display for FilterWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Filter {}: {}",
+            self.filter_index.saturating_add(1),
+            self.kind
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterWarningKind {
+    /// Every window that the filter matches is also matched by an earlier
+    /// filter (with this zero-based index) that stops evaluation, so the
+    /// filter is never used.
+    ShadowedBy(usize),
+    /// A range whose lower bound is larger than its upper bound, so it can't
+    /// contain any index.
+    InvertedRange { field: &'static str },
+    /// An index set without any values, so it can't contain any index.
+    EmptySet { field: &'static str },
+    /// The filter moves windows to a desktop with a negative index.
+    NegativeTargetDesktop,
+    /// The filter moves windows to a desktop that doesn't exist and won't be
+    /// created.
+    TargetDesktopOutOfRange { desktop_count: u32 },
+    /// The filter only matches windows that are already at the desktop it
+    /// moves windows to.
+    MoveToSameDesktop,
+    /// A pattern that can't match any text, for example an invalid regular
+    /// expression.
+    InvalidPattern {
+        field: &'static str,
+        error: Arc<str>,
+    },
+    /// An "any" condition without inner conditions is never fulfilled.
+    EmptyAnyCondition,
+}
+impl fmt::Display for FilterWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterWarningKind::ShadowedBy(index) => write!(
+                f,
+                "never used since filter {} matches the same windows",
+                index.saturating_add(1)
+            ),
+            FilterWarningKind::InvertedRange { field } => {
+                write!(f, "{field} range has a lower bound above its upper bound")
+            }
+            FilterWarningKind::EmptySet { field } => {
+                write!(f, "{field} set doesn't contain any index")
+            }
+            FilterWarningKind::NegativeTargetDesktop => write!(f, "target desktop is negative"),
+            FilterWarningKind::TargetDesktopOutOfRange { desktop_count } => write!(
+                f,
+                "target desktop doesn't exist since there are only {desktop_count} desktops"
+            ),
+            FilterWarningKind::MoveToSameDesktop => write!(
+                f,
+                "only matches windows that are already at the target desktop"
+            ),
+            FilterWarningKind::InvalidPattern { field, error } => {
+                write!(f, "{field} pattern never matches: {error}")
+            }
+            FilterWarningKind::EmptyAnyCondition => {
+                write!(f, "has an \"any\" condition without inner conditions")
+            }
+        }
+    }
+}
+
+/// An invalid pattern in [`WindowExclusions`] that was found by
+/// [`lint_exclusions`]. Such patterns exclude every window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExclusionWarning {
+    /// Either `"process name"` or `"window title"`.
+    pub field: &'static str,
+    pub pattern: Arc<str>,
+    pub error: Arc<str>,
+}
+impl fmt::Display for ExclusionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Excluded {} pattern {:?} is invalid so all windows are excluded: {}",
+            self.field, self.pattern, self.error
+        )
+    }
+}
+
+/// Find invalid patterns in window exclusions.
+pub fn lint_exclusions(exclusions: &WindowExclusions) -> Vec<ExclusionWarning> {
+    let fields = [
+        ("process name", &exclusions.process_names),
+        ("window title", &exclusions.window_titles),
+    ];
+    fields
+        .into_iter()
+        .flat_map(|(field, patterns)| {
+            patterns.iter().filter_map(move |pattern| {
+                Some(ExclusionWarning {
+                    field,
+                    pattern: pattern.pattern().clone(),
+                    error: Arc::from(pattern.error()?),
+                })
+            })
+        })
+        .collect()
+}
+
+/// Check filters for problems. If `desktop_count` is known then filters are
+/// also checked for targeting desktops that don't exist.
+///
+/// Disabled filters and filters in disabled groups are ignored.
+pub fn lint_filters(
+    filters: &[WindowFilter],
+    groups: &[FilterGroup],
+    desktop_count: Option<u32>,
+) -> Vec<FilterWarning> {
+    let is_enabled = |filter: &WindowFilter| {
+        filter.action != FilterAction::Disabled && FilterGroup::is_enabled(groups, &filter.group)
+    };
+    let mut warnings = Vec::new();
+    for (filter_index, filter) in filters.iter().enumerate() {
+        if !is_enabled(filter) {
+            continue;
+        }
+        let mut warn = |kind| warnings.push(FilterWarning { filter_index, kind });
+
+        if let Some(earlier) = filters[..filter_index].iter().position(|earlier| {
+            is_enabled(earlier)
+                && !earlier.continue_after_match
+                // These filters can skip windows that they otherwise match:
+                && !earlier.only_first_time
+                && earlier.settle_delay_ms == 0
+                && !earlier.conditions.as_ref().is_some_and(has_schedule)
+                && is_shadowed_by(filter, earlier)
+        }) {
+            warn(FilterWarningKind::ShadowedBy(earlier));
+        }
+
+        check_range(&mut warn, "window index", filter.window_index);
+        check_range(&mut warn, "desktop index", filter.desktop_index);
+        check_set(&mut warn, "window index", filter.window_index_set.as_ref());
+        check_set(
+            &mut warn,
+            "desktop index",
+            filter.desktop_index_set.as_ref(),
+        );
+        check_pattern(&mut warn, "window title", &filter.window_title);
+        check_pattern(&mut warn, "process name", &filter.process_name);
+        check_pattern(&mut warn, "window class", &filter.window_class);
+        check_pattern(&mut warn, "executable path", &filter.executable_path);
+        if let Some(conditions) = &filter.conditions {
+            check_condition(&mut warn, conditions);
+        }
+        if let Some(distribution) = &filter.distribute_to {
+            check_range(&mut warn, "distribution desktop", distribution.desktops);
+        }
+
+        let uses_target_index = match &filter.dynamic_target {
+            None => true,
+            Some(DesktopTarget::Named { if_missing, .. }) => {
+                *if_missing == MissingDesktop::UseIndex
+            }
+            Some(_) => false,
+        };
+        if matches!(
+            filter.action,
+            FilterAction::Move | FilterAction::UnpinAndMove
+        ) && uses_target_index
+        {
+            if filter.target_desktop < 0 {
+                warn(FilterWarningKind::NegativeTargetDesktop);
+            } else if let Some(desktop_count) = desktop_count {
+                if filter.target_desktop >= i64::from(desktop_count)
+                    && filter.create_missing_desktops.is_none()
+                {
+                    warn(FilterWarningKind::TargetDesktopOutOfRange { desktop_count });
+                }
+            }
+        }
+        if filter.action == FilterAction::Move
+            && filter.dynamic_target.is_none()
+            && only_matches_desktop(filter, filter.target_desktop)
+        {
+            warn(FilterWarningKind::MoveToSameDesktop);
+        }
+    }
+    warnings
+}
+
+/// `true` if the filter only matches windows at the desktop with the
+/// specified zero-based index. Index conditions that can be stored as ranges
+/// or sets are also considered.
+fn only_matches_desktop(filter: &WindowFilter, desktop: i64) -> bool {
+    let filter = filter.clone().flatten_conditions().flatten_index_sets();
+    let range = filter.desktop_index;
+    match &filter.desktop_index_set {
+        Some(set) if !set.negated => {
+            let mut values = set.values.iter().filter(|&&value| range.contains(value));
+            values.clone().next().is_some() && values.all(|&value| value == desktop)
+        }
+        _ => range.lower_bound == Some(desktop) && range.upper_bound == Some(desktop),
+    }
+}
+
+/// `true` if the condition depends on the time of day or day of the week.
+fn has_schedule(condition: &FilterCondition) -> bool {
+    match condition {
+        FilterCondition::All(conditions) | FilterCondition::Any(conditions) => {
+            conditions.iter().any(has_schedule)
+        }
+        FilterCondition::Not(condition) => has_schedule(condition),
+        FilterCondition::Schedule(_) => true,
+        _ => false,
+    }
+}
+
+/// `true` if every window that `filter` matches is also matched by `earlier`.
+/// This is a conservative check, so it might miss some shadowed filters.
+///
+/// Filters in different groups are never considered shadowed since the groups
+/// can be applied separately.
+fn is_shadowed_by(filter: &WindowFilter, earlier: &WindowFilter) -> bool {
+    let covers_pattern =
+        |earlier: &TextPattern, pattern: &TextPattern| earlier.is_match_all() || earlier == pattern;
+    earlier.group == filter.group
+        && earlier.window_index.contains_range(filter.window_index)
+        && earlier.desktop_index.contains_range(filter.desktop_index)
+        && (earlier.window_index_set.is_none()
+            || earlier.window_index_set == filter.window_index_set)
+        && (earlier.desktop_index_set.is_none()
+            || earlier.desktop_index_set == filter.desktop_index_set)
+        && covers_pattern(&earlier.window_title, &filter.window_title)
+        && covers_pattern(&earlier.process_name, &filter.process_name)
+        && covers_pattern(&earlier.window_class, &filter.window_class)
+        && covers_pattern(&earlier.executable_path, &filter.executable_path)
+        && (earlier.conditions.is_none() || earlier.conditions == filter.conditions)
+}
+
+fn check_range(warn: &mut impl FnMut(FilterWarningKind), field: &'static str, range: IntegerRange) {
+    if range.is_inverted() {
+        warn(FilterWarningKind::InvertedRange { field });
+    }
+}
+
+fn check_set(
+    warn: &mut impl FnMut(FilterWarningKind),
+    field: &'static str,
+    set: Option<&IntegerSet>,
+) {
+    if set.is_some_and(IntegerSet::is_empty) {
+        warn(FilterWarningKind::EmptySet { field });
+    }
+}
+
+fn check_pattern(
+    warn: &mut impl FnMut(FilterWarningKind),
+    field: &'static str,
+    pattern: &TextPattern,
+) {
+    if let Some(error) = pattern.error() {
+        warn(FilterWarningKind::InvalidPattern {
+            field,
+            error: Arc::from(error),
+        });
+    }
+}
+
+fn check_condition(warn: &mut impl FnMut(FilterWarningKind), condition: &FilterCondition) {
+    match condition {
+        FilterCondition::All(conditions) => {
+            for condition in conditions {
+                check_condition(warn, condition);
+            }
+        }
+        FilterCondition::Any(conditions) => {
+            if conditions.is_empty() {
+                warn(FilterWarningKind::EmptyAnyCondition);
+            }
+            for condition in conditions {
+                check_condition(warn, condition);
+            }
+        }
+        FilterCondition::Not(condition) => check_condition(warn, condition),
+        FilterCondition::WindowIndex(range) => check_range(warn, "window index", *range),
+        FilterCondition::DesktopIndex(range) => check_range(warn, "desktop index", *range),
+        FilterCondition::ProcessWindowIndex(range) => {
+            check_range(warn, "process window index", *range)
+        }
+        FilterCondition::ProcessWindowCount(range) => {
+            check_range(warn, "process window count", *range)
+        }
+        FilterCondition::WindowTitle(pattern) => check_pattern(warn, "window title", pattern),
+        FilterCondition::ProcessName(pattern) => check_pattern(warn, "process name", pattern),
+        FilterCondition::WindowClass(pattern) => check_pattern(warn, "window class", pattern),
+        FilterCondition::ExecutablePath(pattern) => check_pattern(warn, "executable path", pattern),
+        FilterCondition::NamedDesktopRange { first, last } => {
+            for pattern in [first, last].into_iter().flatten() {
+                check_pattern(warn, "desktop name", pattern);
+            }
+        }
+        FilterCondition::DesktopName(pattern) => check_pattern(warn, "desktop name", pattern),
+        FilterCondition::Schedule(_) | FilterCondition::PinState(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        window_filter::Schedule,
+        window_info::{VirtualDesktopInfo, WindowInfo},
+    };
+
+    fn filter(action: FilterAction) -> WindowFilter {
+        WindowFilter {
+            process_name: TextPattern::from("notepad"),
+            action,
+            ..WindowFilter::default()
+        }
+    }
+
+    fn kinds(filters: &[WindowFilter], groups: &[FilterGroup]) -> Vec<(usize, FilterWarningKind)> {
+        lint_filters(filters, groups, None)
+            .into_iter()
+            .map(|warning| (warning.filter_index, warning.kind))
+            .collect()
+    }
+
+    #[test]
+    fn shadowed_filter() {
+        let filters = [filter(FilterAction::Pin), filter(FilterAction::Unpin)];
+        assert_eq!(
+            kinds(&filters, &[]),
+            [(1, FilterWarningKind::ShadowedBy(0))]
+        );
+    }
+
+    #[test]
+    fn filters_that_can_skip_windows_dont_shadow() {
+        let earlier = [
+            WindowFilter {
+                only_first_time: true,
+                ..filter(FilterAction::Pin)
+            },
+            WindowFilter {
+                settle_delay_ms: 1000,
+                ..filter(FilterAction::Pin)
+            },
+            WindowFilter {
+                conditions: Some(FilterCondition::Schedule(Schedule::default())),
+                ..filter(FilterAction::Pin)
+            },
+            WindowFilter {
+                conditions: Some(FilterCondition::Not(Box::new(FilterCondition::Schedule(
+                    Schedule::default(),
+                )))),
+                ..filter(FilterAction::Pin)
+            },
+        ];
+        for earlier in earlier {
+            let filters = [earlier, filter(FilterAction::Unpin)];
+            assert_eq!(kinds(&filters, &[]), []);
+        }
+    }
+
+    #[test]
+    fn filters_in_disabled_groups_are_ignored() {
+        let groups = [FilterGroup {
+            name: Arc::from("Off"),
+            enabled: false,
+        }];
+        let disabled = WindowFilter {
+            group: Arc::from("Off"),
+            ..filter(FilterAction::Pin)
+        };
+        // Neither reported nor shadowing other filters in the same group:
+        let filters = [
+            disabled.clone(),
+            WindowFilter {
+                desktop_index: IntegerRange {
+                    lower_bound: Some(2),
+                    upper_bound: Some(1),
+                },
+                ..disabled
+            },
+        ];
+        assert_eq!(kinds(&filters, &groups), []);
+        assert_eq!(
+            kinds(&filters, &[]),
+            [
+                (1, FilterWarningKind::ShadowedBy(0)),
+                (
+                    1,
+                    FilterWarningKind::InvertedRange {
+                        field: "desktop index"
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_exclusions_exclude_every_window() {
+        let exclusions = WindowExclusions {
+            process_names: vec![WindowExclusions::process_name_pattern("vpnclient")],
+            window_titles: vec![
+                WindowExclusions::window_title_pattern("^Secret"),
+                WindowExclusions::window_title_pattern("("),
+            ],
+        };
+        let warnings = lint_exclusions(&exclusions);
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.field == "window title"));
+        assert!(warnings.iter().any(|warning| &*warning.pattern == "("));
+        // Without regex support every title pattern is invalid:
+        let expected = if cfg!(feature = "filter_regex") { 1 } else { 2 };
+        assert_eq!(warnings.len(), expected);
+
+        let window = WindowInfo::for_test(
+            1,
+            "notepad",
+            "Notepad",
+            VirtualDesktopInfo::AtDesktop { index: 0 },
+        );
+        assert!(exclusions.is_excluded(&window));
+        let valid = WindowExclusions {
+            window_titles: Vec::new(),
+            ..exclusions
+        };
+        assert!(lint_exclusions(&valid).is_empty());
+        assert!(!valid.is_excluded(&window));
+    }
+
+    #[test]
+    fn move_to_same_desktop() {
+        let at_desktop = |desktop_index, desktop_index_set, conditions| WindowFilter {
+            desktop_index,
+            desktop_index_set,
+            conditions,
+            target_desktop: 1,
+            ..filter(FilterAction::Move)
+        };
+        let single = IntegerRange {
+            lower_bound: Some(1),
+            upper_bound: Some(1),
+        };
+        let same = [
+            at_desktop(single, None, None),
+            at_desktop(
+                IntegerRange::default(),
+                Some(IntegerSet::new([1], false)),
+                None,
+            ),
+            at_desktop(
+                IntegerRange {
+                    lower_bound: Some(1),
+                    upper_bound: None,
+                },
+                Some(IntegerSet::new([0, 1], false)),
+                None,
+            ),
+            at_desktop(
+                IntegerRange::default(),
+                None,
+                Some(FilterCondition::DesktopIndex(single)),
+            ),
+        ];
+        for filter in same {
+            assert_eq!(
+                kinds(std::slice::from_ref(&filter), &[]),
+                [(0, FilterWarningKind::MoveToSameDesktop)],
+                "{filter:?}"
+            );
+        }
+        let different = [
+            at_desktop(IntegerRange::default(), None, None),
+            at_desktop(
+                IntegerRange::default(),
+                Some(IntegerSet::new([0, 1], false)),
+                None,
+            ),
+            at_desktop(
+                IntegerRange::default(),
+                Some(IntegerSet::new([0], true)),
+                None,
+            ),
+        ];
+        for filter in different {
+            assert_eq!(kinds(std::slice::from_ref(&filter), &[]), [], "{filter:?}");
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Find the filters that apply to a window without checking every filter, see
+//! [`FilterMatcher`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use crate::{
+    window_filter::{
+        FilterAction, FilterContext, FoundActions, RequiredLiteral, TextMatchOptions, TextPattern,
+        WindowFilter,
+    },
+    window_info::WindowInfo,
+};
+
+/// A list of filters that has been prepared so that most filters that can't
+/// apply to a window are skipped without checking all of their patterns.
+///
+/// Filters are indexed by literal text that a window's process name or title
+/// must have for the filter to match, see [`TextPattern::required_literal`].
+/// The remaining candidates are then checked the same way as
+/// [`WindowFilter::find_actions`] does, so the results are always identical.
+#[derive(Debug)]
+pub struct FilterMatcher {
+    filters: Arc<[WindowFilter]>,
+    /// Indexes of enabled filters that couldn't be indexed and therefore must
+    /// always be checked.
+    unindexed: Vec<usize>,
+    process_names: PatternIndex,
+    window_titles: PatternIndex,
+}
+impl FilterMatcher {
+    pub fn new(filters: Arc<[WindowFilter]>) -> Self {
+        let mut unindexed = Vec::new();
+        let mut process_names = PatternIndex::default();
+        let mut window_titles = PatternIndex::default();
+        for (ix, filter) in filters.iter().enumerate() {
+            if filter.action == FilterAction::Disabled {
+                continue;
+            }
+            if !process_names.insert(&filter.process_name, ix)
+                && !window_titles.insert(&filter.window_title, ix)
+            {
+                unindexed.push(ix);
+            }
+        }
+        Self {
+            filters,
+            unindexed,
+            process_names,
+            window_titles,
+        }
+    }
+    /// The filters that this matcher was created from.
+    pub fn filters(&self) -> &Arc<[WindowFilter]> {
+        &self.filters
+    }
+    /// Indexes of filters that might apply to a window, ordered by index.
+    fn candidates(&self, window: &WindowInfo) -> Vec<usize> {
+        let mut candidates = self.unindexed.clone();
+        self.process_names
+            .find(&window.process_name, &mut candidates);
+        self.window_titles.find(&window.title, &mut candidates);
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+    /// Same as [`WindowFilter::find_first_action`].
+    pub fn find_first_action(
+        &self,
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> Option<&WindowFilter> {
+        WindowFilter::find_first_action_among(
+            self.candidates(window)
+                .into_iter()
+                .map(|ix| (ix, &self.filters[ix])),
+            window_index,
+            window,
+            context,
+        )
+    }
+    /// Same as [`WindowFilter::find_actions_among`] for all filters.
+    pub fn find_actions(
+        &self,
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> FoundActions<'_> {
+        WindowFilter::find_actions_among(
+            self.candidates(window)
+                .into_iter()
+                .map(|ix| (ix, &self.filters[ix])),
+            window_index,
+            window,
+            context,
+        )
+    }
+}
+
+/// Filter indexes grouped by the literal text that a field must have for the
+/// filter to match. Patterns with different options are kept apart since the
+/// checked text must be prepared differently for them.
+#[derive(Debug, Default)]
+struct PatternIndex {
+    by_options: HashMap<TextMatchOptions, LiteralIndex>,
+}
+impl PatternIndex {
+    /// Returns `false` if the pattern doesn't have any required literal text
+    /// and so the filter wasn't added.
+    fn insert(&mut self, pattern: &TextPattern, filter_index: usize) -> bool {
+        let Some(literal) = pattern.required_literal() else {
+            return false;
+        };
+        let index = self.by_options.entry(pattern.options()).or_default();
+        let (map, text) = match literal {
+            RequiredLiteral::Exact(text) => {
+                index
+                    .exact
+                    .entry(Box::from(text))
+                    .or_default()
+                    .push(filter_index);
+                return true;
+            }
+            RequiredLiteral::Prefix(text) => (&mut index.prefixes, text),
+            RequiredLiteral::Suffix(text) => (&mut index.suffixes, text),
+        };
+        map.entry(text.len())
+            .or_default()
+            .entry(Box::from(text))
+            .or_default()
+            .push(filter_index);
+        true
+    }
+    /// Add the indexes of filters whose literal text is part of `text`.
+    fn find(&self, text: &str, candidates: &mut Vec<usize>) {
+        for (options, index) in &self.by_options {
+            index.find(&options.prepare(text), candidates);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LiteralIndex {
+    exact: HashMap<Box<str>, Vec<usize>>,
+    /// Required prefixes grouped by their length in bytes, so that only
+    /// prefixes of the checked text with those lengths need to be looked up.
+    prefixes: BTreeMap<usize, HashMap<Box<str>, Vec<usize>>>,
+    /// Required suffixes grouped by their length in bytes.
+    suffixes: BTreeMap<usize, HashMap<Box<str>, Vec<usize>>>,
+}
+impl LiteralIndex {
+    fn find(&self, text: &str, candidates: &mut Vec<usize>) {
+        if let Some(found) = self.exact.get(text) {
+            candidates.extend_from_slice(found);
+        }
+        for (&len, prefixes) in self.prefixes.range(..=text.len()) {
+            if !text.is_char_boundary(len) {
+                continue;
+            }
+            if let Some(found) = prefixes.get(&text[..len]) {
+                candidates.extend_from_slice(found);
+            }
+        }
+        for (&len, suffixes) in self.suffixes.range(..=text.len()) {
+            let start = text.len() - len;
+            if !text.is_char_boundary(start) {
+                continue;
+            }
+            if let Some(found) = suffixes.get(&text[start..]) {
+                candidates.extend_from_slice(found);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        window_filter::{TextMatchMode, TextNormalization},
+        window_info::VirtualDesktopInfo,
+    };
+
+    fn patterns() -> Vec<TextPattern> {
+        let glob = |pattern: &str| TextPattern::new(Arc::from(pattern));
+        let ignore_case = TextMatchOptions {
+            ignore_case: true,
+            ..Default::default()
+        };
+        vec![
+            TextPattern::default(),
+            // Prefixes and suffixes that end inside multi-byte characters of
+            // some texts:
+            glob("Ä\n"),
+            glob("Är\n"),
+            glob("ab\n"),
+            glob("日\n"),
+            glob("\n語 - Code"),
+            glob("\nfox"),
+            glob("\n - \n"),
+            glob("firefox"),
+            glob("ärende\n").with_options(ignore_case),
+            glob("\nSLACK").with_options(ignore_case),
+            glob("école\n").with_options(TextMatchOptions {
+                normalization: TextNormalization::Nfc,
+                ..Default::default()
+            }),
+            glob("Inbox - slack").with_options(TextMatchOptions {
+                collapse_whitespace: true,
+                ..Default::default()
+            }),
+            glob("日本語 - Code").with_mode(TextMatchMode::Exact),
+            glob("inbox - slack")
+                .with_mode(TextMatchMode::Exact)
+                .with_options(ignore_case),
+            glob("^Ä.*fox$").with_mode(TextMatchMode::Regex),
+            glob("(?i)^inbox").with_mode(TextMatchMode::Regex),
+        ]
+    }
+
+    fn windows() -> Vec<WindowInfo> {
+        [
+            ("firefox", "Ärende - firefox"),
+            ("Code", "日本語 - Code"),
+            ("notepad", "e\u{301}cole - notepad"),
+            ("notepad", "école - notepad"),
+            ("slack", "Inbox  -   slack"),
+            ("SLACK", "INBOX - SLACK"),
+            ("ä", "ä"),
+            ("ab", "a"),
+            ("日本", ""),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(ix, (process_name, title))| {
+            WindowInfo::for_test(
+                ix as isize + 1,
+                process_name,
+                title,
+                VirtualDesktopInfo::AtDesktop { index: 0 },
+            )
+        })
+        .collect()
+    }
+
+    fn filters(continue_after_match: bool) -> Vec<WindowFilter> {
+        let actions = [
+            FilterAction::Move,
+            FilterAction::Minimize,
+            FilterAction::Disabled,
+        ];
+        let mut filters = Vec::new();
+        for (ix, pattern) in patterns().into_iter().enumerate() {
+            let action = actions[ix % actions.len()];
+            filters.push(WindowFilter {
+                window_title: pattern.clone(),
+                action,
+                continue_after_match,
+                ..Default::default()
+            });
+            filters.push(WindowFilter {
+                process_name: pattern,
+                action: actions[(ix + 1) % actions.len()],
+                continue_after_match,
+                ..Default::default()
+            });
+        }
+        filters
+    }
+
+    /// The matcher must find exactly the same filters as checking every
+    /// filter for every window.
+    #[test]
+    fn same_result_as_checking_every_filter() {
+        let context = FilterContext::default();
+        for continue_after_match in [true, false] {
+            let filters = Arc::<[WindowFilter]>::from(filters(continue_after_match));
+            // Check each filter on its own so that later filters can't hide
+            // mistakes for earlier ones:
+            let single_filters = filters
+                .iter()
+                .map(|filter| Arc::<[WindowFilter]>::from([filter.clone()]));
+            for filters in single_filters.chain([filters.clone()]) {
+                let matcher = FilterMatcher::new(filters.clone());
+                for (ix, window) in windows().iter().enumerate() {
+                    let window_index = ix as i32;
+                    assert_eq!(
+                        matcher.find_actions(window_index, window, &context),
+                        WindowFilter::find_actions_among(
+                            filters.iter().enumerate(),
+                            window_index,
+                            window,
+                            &context
+                        ),
+                        "different actions for {window:?} with filters {filters:?}"
+                    );
+                    assert_eq!(
+                        matcher
+                            .find_first_action(window_index, window, &context)
+                            .map(|filter| filter as *const _),
+                        WindowFilter::find_first_action(&filters, window_index, window, &context)
+                            .map(|filter| filter as *const _),
+                        "different first action for {window:?} with filters {filters:?}"
+                    );
+                }
+            }
+        }
+    }
+}
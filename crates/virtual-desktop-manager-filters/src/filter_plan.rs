@@ -0,0 +1,1154 @@
+//! Decide what applying filters would do to windows without changing anything,
+//! so that the result can be previewed before it is executed by a
+//! [`WindowController`].
+
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use crate::{
+    filter_matcher::FilterMatcher,
+    window_filter::{
+        DistributionMode, FilterAction, FilterContext, ProcessWindows, ResolvedTarget, WindowFilter,
+    },
+    window_info::{VirtualDesktopInfo, WindowInfo},
+};
+
+/// Something that should be done when applying filters. Windows are referred
+/// to by their index in [`FilterPlan::windows`] and desktops by their
+/// zero-based index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedOperation {
+    /// Create a new virtual desktop which is expected to get this index.
+    CreateDesktop { index: u32, name: Option<Arc<str>> },
+    /// Move a window to another virtual desktop.
+    Move { window: usize, target: u32 },
+    /// Unpin a window so that it is only visible on a single desktop.
+    Unpin { window: usize },
+    /// Pin a window so that it is visible on all desktops.
+    Pin { window: usize },
+    /// Unpin all windows of the window's process.
+    UnpinApp { window: usize },
+    /// Pin all windows of the window's process.
+    PinApp { window: usize },
+    /// Change a window's show state, close it or bring it to the foreground.
+    Window {
+        window: usize,
+        command: WindowCommand,
+    },
+    /// Show a notification that a window was moved to another desktop.
+    Notify { window: usize, target: u32 },
+    /// Stop a window from flashing in the taskbar. The window is hidden while
+    /// doing so, which means it can also be moved to another desktop.
+    StopFlashing { window: usize, target: Option<u32> },
+    /// A filter would have affected the window but it was left alone since all
+    /// windows of its process are pinned, see
+    /// [`WindowFilter::apply_to_app_pinned`].
+    SkippedAppPinned { window: usize },
+    /// A filter would have affected the window but it was left alone since it
+    /// matches the [`FilterContext::exclusions`].
+    SkippedExcluded { window: usize },
+    /// The filter with index `filter` acted on the window. It should be
+    /// recorded in [`FilterContext::seen_windows`] if every operation for the
+    /// window succeeded, see [`WindowFilter::only_first_time`].
+    FilterApplied { window: usize, filter: usize },
+}
+
+/// Something that can be done to a window other than changing its virtual
+/// desktop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowCommand {
+    Minimize,
+    Restore,
+    Maximize,
+    Close,
+    /// Bring the window to the foreground.
+    Focus,
+}
+impl WindowCommand {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WindowCommand::Minimize => "Minimize",
+            WindowCommand::Restore => "Restore",
+            WindowCommand::Maximize => "Maximize",
+            WindowCommand::Close => "Close",
+            WindowCommand::Focus => "Focus",
+        }
+    }
+}
+impl fmt::Display for WindowCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Platform specific code that changes real windows and virtual desktops.
+/// Plans only describe operations so they can be created and inspected
+/// without affecting anything, see [`execute_plan`].
+pub trait WindowController {
+    type Error: fmt::Debug;
+
+    fn run_command(
+        &mut self,
+        window: &WindowInfo,
+        command: WindowCommand,
+    ) -> Result<(), Self::Error>;
+    /// Create a new virtual desktop which is expected to get this index.
+    fn create_desktop(&mut self, index: u32, name: Option<&str>) -> Result<(), Self::Error>;
+    fn move_to_desktop(&mut self, window: &WindowInfo, desktop: u32) -> Result<(), Self::Error>;
+    fn pin_window(&mut self, window: &WindowInfo) -> Result<(), Self::Error>;
+    fn unpin_window(&mut self, window: &WindowInfo) -> Result<(), Self::Error>;
+    fn pin_app(&mut self, window: &WindowInfo) -> Result<(), Self::Error>;
+    fn unpin_app(&mut self, window: &WindowInfo) -> Result<(), Self::Error>;
+    /// Stop windows from flashing in the taskbar. Windows with a target
+    /// desktop are moved there at the same time.
+    fn stop_flashing(&mut self, windows: &[(&WindowInfo, Option<u32>)]) -> Result<(), Self::Error>;
+}
+
+/// Operations that should be preformed in order to apply filters.
+#[derive(Debug, Clone, Default)]
+pub struct FilterPlan {
+    /// The windows that the filters were checked against, ordered by their
+    /// window index.
+    pub windows: Vec<WindowInfo>,
+    pub operations: Vec<PlannedOperation>,
+    /// Some filters are waiting for windows to settle and should be checked
+    /// again after this delay, see [`WindowFilter::settle_delay_ms`].
+    pub recheck_after: Option<Duration>,
+}
+impl FilterPlan {
+    fn display_window(&self, window: usize) -> impl fmt::Display + '_ {
+        struct Helper<'a>(Option<&'a WindowInfo>);
+        impl fmt::Display for Helper<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0 {
+                    Some(window) => write!(f, "{:?} ({})", window.title, window.process_name),
+                    None => write!(f, "<unknown window>"),
+                }
+            }
+        }
+        Helper(self.windows.get(window))
+    }
+}
+/// Lists the planned operations, one per line. Desktops are displayed with
+/// one-based indexing.
+impl fmt::Display for FilterPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.operations.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        for operation in &self.operations {
+            match operation {
+                PlannedOperation::CreateDesktop { index, name } => {
+                    write!(f, "Create desktop {}", index.saturating_add(1))?;
+                    if let Some(name) = name {
+                        write!(f, " named {name:?}")?;
+                    }
+                }
+                PlannedOperation::Move { window, target } => write!(
+                    f,
+                    "Move {} to desktop {}",
+                    self.display_window(*window),
+                    target.saturating_add(1)
+                )?,
+                PlannedOperation::Unpin { window } => {
+                    write!(f, "Unpin {}", self.display_window(*window))?
+                }
+                PlannedOperation::Pin { window } => {
+                    write!(f, "Pin {}", self.display_window(*window))?
+                }
+                PlannedOperation::UnpinApp { window } => {
+                    write!(f, "Unpin the app of {}", self.display_window(*window))?
+                }
+                PlannedOperation::PinApp { window } => {
+                    write!(f, "Pin the app of {}", self.display_window(*window))?
+                }
+                PlannedOperation::Window { window, command } => {
+                    write!(f, "{command} {}", self.display_window(*window))?
+                }
+                PlannedOperation::Notify { window, target } => write!(
+                    f,
+                    "Notify that {} was moved to desktop {}",
+                    self.display_window(*window),
+                    target.saturating_add(1)
+                )?,
+                PlannedOperation::StopFlashing { window, target } => {
+                    write!(f, "Stop flashing {}", self.display_window(*window))?;
+                    if let Some(target) = target {
+                        write!(f, " on desktop {}", target.saturating_add(1))?;
+                    }
+                }
+                PlannedOperation::SkippedAppPinned { window } => write!(
+                    f,
+                    "Skip {} since all windows of its process are pinned",
+                    self.display_window(*window)
+                )?,
+                PlannedOperation::SkippedExcluded { window } => write!(
+                    f,
+                    "Skip {} since it is excluded from all filters",
+                    self.display_window(*window)
+                )?,
+                PlannedOperation::FilterApplied { window, filter } => write!(
+                    f,
+                    "Remember that filter {} was applied to {}",
+                    filter.saturating_add(1),
+                    self.display_window(*window)
+                )?,
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a window will be after the operations that have been planned so far.
+#[derive(Debug, Clone, Copy)]
+enum Placement {
+    Pinned,
+    /// All windows of the process are pinned.
+    AppPinned,
+    /// At a desktop with this index, `None` if the index is unknown.
+    AtDesktop(Option<u32>),
+}
+
+/// Determine what should be done to apply filters to some windows. The
+/// `windows` should be ordered by their window index, which is the order
+/// they were enumerated in.
+///
+/// If `stop_flashing` is `true` then windows that are affected by a filter
+/// are also stopped from flashing (unless the filter overrides this, see
+/// [`WindowFilter::stop_flashing`]), and if `stop_flashing_globally` is `true`
+/// then all windows are. No filters are applied if `filters` is `None`.
+///
+/// Windows that match [`FilterContext::exclusions`] are never affected.
+pub fn plan_filters(
+    filters: Option<&FilterMatcher>,
+    windows: Vec<WindowInfo>,
+    context: FilterContext,
+    stop_flashing: bool,
+    stop_flashing_globally: bool,
+) -> FilterPlan {
+    let mut planner = Planner {
+        context,
+        operations: Vec::new(),
+        stop_flashing,
+        window_counts: HashMap::new(),
+        distributed: HashMap::new(),
+    };
+    for window in &windows {
+        if let VirtualDesktopInfo::AtDesktop { index, .. } = window.virtual_desktop {
+            *planner.window_counts.entry(index).or_default() += 1;
+        }
+    }
+    planner.context.process_windows = ProcessWindows::from_windows(&windows);
+    // Processes whose windows were pinned (`true`) or unpinned by earlier
+    // operations:
+    let mut app_pinned = HashMap::new();
+    let mut recheck_after = None::<Duration>;
+    let longest_settle_delay = filters
+        .into_iter()
+        .flat_map(|filters| filters.filters().iter())
+        .filter(|filter| filter.action != FilterAction::Disabled)
+        .map(WindowFilter::settle_delay)
+        .max()
+        .unwrap_or_default();
+    for (ix, window) in windows.iter().enumerate() {
+        if planner.context.only_unsettled
+            && planner
+                .context
+                .seen_windows
+                .applied_since_settled(window, longest_settle_delay)
+        {
+            // Already handled when filters were applied earlier:
+            continue;
+        }
+        // The desktop a window should be at after it stopped flashing:
+        let mut flashing_target = stop_flashing_globally.then_some(match window.virtual_desktop {
+            VirtualDesktopInfo::AtDesktop { index, .. } => Some(index),
+            _ => None,
+        });
+        if planner.context.exclusions.is_excluded(window) {
+            // Never touch excluded windows, not even to stop them from flashing:
+            let would_act = filters.is_some_and(|filters| {
+                filters
+                    .find_actions(ix as i32, window, &planner.context)
+                    .actions
+                    .iter()
+                    .any(|(_, filter)| filter.action != FilterAction::Nothing)
+            });
+            if would_act {
+                planner
+                    .operations
+                    .push(PlannedOperation::SkippedExcluded { window: ix });
+            }
+            continue;
+        }
+        if let Some(filters) = filters {
+            let found = filters.find_actions(ix as i32, window, &planner.context);
+            if let Some(delay) = found.recheck_after {
+                recheck_after = Some(recheck_after.map_or(delay, |prev| prev.min(delay)));
+            }
+
+            let app_pin_change = app_pinned.get(&window.process_id).copied();
+            let mut placement = match (window.virtual_desktop, app_pin_change) {
+                (_, Some(true)) | (VirtualDesktopInfo::AppPinned, None) => Placement::AppPinned,
+                (VirtualDesktopInfo::AtDesktop { index, .. }, _) => {
+                    Placement::AtDesktop(Some(index))
+                }
+                (VirtualDesktopInfo::WindowPinned, _) => Placement::Pinned,
+                // Unpinned apps remain on the current desktop:
+                (VirtualDesktopInfo::AppPinned, Some(false)) => {
+                    Placement::AtDesktop(planner.context.current_desktop)
+                }
+            };
+            let mut skipped_app_pinned = false;
+            for (filter_index, filter) in found.actions {
+                if matches!(placement, Placement::AppPinned)
+                    && !filter.apply_to_app_pinned
+                    && !filter.action.affects_app()
+                {
+                    // Don't interact with process that have all of their windows pinned.
+                    if filter.action != FilterAction::Nothing && !skipped_app_pinned {
+                        skipped_app_pinned = true;
+                        planner
+                            .operations
+                            .push(PlannedOperation::SkippedAppPinned { window: ix });
+                    }
+                    continue;
+                }
+                let was_app_pinned = matches!(placement, Placement::AppPinned);
+                let acted = planner.plan_action(
+                    filter_index,
+                    filter,
+                    ix,
+                    &mut placement,
+                    &mut flashing_target,
+                );
+                if acted && filter.only_first_time {
+                    planner.operations.push(PlannedOperation::FilterApplied {
+                        window: ix,
+                        filter: filter_index,
+                    });
+                }
+                let is_app_pinned = matches!(placement, Placement::AppPinned);
+                if was_app_pinned != is_app_pinned {
+                    app_pinned.insert(window.process_id, is_app_pinned);
+                }
+            }
+        }
+        if let Some(target) = flashing_target {
+            planner
+                .operations
+                .push(PlannedOperation::StopFlashing { window: ix, target });
+        }
+    }
+    FilterPlan {
+        windows,
+        operations: planner.operations,
+        recheck_after,
+    }
+}
+
+/// State that is shared while planning the actions for all windows.
+struct Planner {
+    context: FilterContext,
+    operations: Vec<PlannedOperation>,
+    /// Default for [`WindowFilter::stop_flashing`].
+    stop_flashing: bool,
+    /// The number of windows at each desktop after the operations that have
+    /// been planned so far.
+    window_counts: HashMap<u32, usize>,
+    /// The number of windows that each filter (identified by its index) has
+    /// distributed so far.
+    distributed: HashMap<usize, u32>,
+}
+impl Planner {
+    /// Plan a single filter action for a window. If all windows should stop
+    /// flashing then `flashing_target` is updated instead of moving the window
+    /// directly.
+    ///
+    /// Returns `false` if the action couldn't be applied to the window, for
+    /// example since its target desktop doesn't exist.
+    fn plan_action(
+        &mut self,
+        filter_index: usize,
+        filter: &WindowFilter,
+        window: usize,
+        placement: &mut Placement,
+        flashing_target: &mut Option<Option<u32>>,
+    ) -> bool {
+        let current_desktop = self.context.current_desktop;
+        let stop_flashing = filter.stop_flashing.unwrap_or(self.stop_flashing);
+        let unpin_window = |placement: &mut Placement, operations: &mut Vec<_>| {
+            match placement {
+                Placement::Pinned => operations.push(PlannedOperation::Unpin { window }),
+                // Only unpinning the app moves the window off every desktop:
+                Placement::AppPinned => operations.push(PlannedOperation::UnpinApp { window }),
+                Placement::AtDesktop(_) => return,
+            }
+            // Unpinned windows remain on the current desktop:
+            *placement = Placement::AtDesktop(current_desktop);
+        };
+        let stop_flashing_without_move =
+            |flashing_target: &mut Option<Option<u32>>, operations: &mut Vec<_>| {
+                if let Some(target) = flashing_target {
+                    *target = None;
+                } else if stop_flashing {
+                    operations.push(PlannedOperation::StopFlashing {
+                        window,
+                        target: None,
+                    });
+                }
+            };
+        let operations = &mut self.operations;
+
+        match filter.action {
+            FilterAction::Move
+            | FilterAction::UnpinAndMove
+            | FilterAction::BringHere
+            | FilterAction::UnpinAndBringHere
+            | FilterAction::Distribute => {
+                if let FilterAction::UnpinAndMove | FilterAction::UnpinAndBringHere = filter.action
+                {
+                    if !matches!(placement, Placement::AtDesktop(_)) {
+                        // Pinned windows are only unpinned, which leaves them
+                        // at the current desktop:
+                        unpin_window(placement, operations);
+                        return true;
+                    }
+                }
+                let Placement::AtDesktop(current) = *placement else {
+                    return false;
+                };
+                let target = if filter.action.brings_here() {
+                    current_desktop
+                } else if filter.action == FilterAction::Distribute {
+                    self.distribute(filter_index, filter, current)
+                } else {
+                    resolve_target(filter, &mut self.context, operations)
+                };
+                let Some(target) = target else {
+                    return false;
+                };
+                *placement = Placement::AtDesktop(Some(target));
+                if current != Some(target) {
+                    if let Some(current) = current {
+                        if let Some(count) = self.window_counts.get_mut(&current) {
+                            *count = count.saturating_sub(1);
+                        }
+                    }
+                    *self.window_counts.entry(target).or_default() += 1;
+                }
+                let operations = &mut self.operations;
+                if let Some(flashing_target) = flashing_target {
+                    *flashing_target = Some(target);
+                } else if current == Some(target) {
+                    // Already at wanted desktop
+                } else if stop_flashing {
+                    operations.push(PlannedOperation::StopFlashing {
+                        window,
+                        target: Some(target),
+                    });
+                } else {
+                    operations.push(PlannedOperation::Move { window, target });
+                }
+                if filter.focus_after_move && current != Some(target) {
+                    operations.push(PlannedOperation::Window {
+                        window,
+                        command: WindowCommand::Focus,
+                    });
+                }
+                if filter.notify_on_move && current != Some(target) {
+                    operations.push(PlannedOperation::Notify { window, target });
+                }
+            }
+            FilterAction::Unpin => {
+                unpin_window(placement, operations);
+                stop_flashing_without_move(flashing_target, operations);
+            }
+            FilterAction::Pin => {
+                if let Placement::AtDesktop(_) = placement {
+                    operations.push(PlannedOperation::Pin { window });
+                    *placement = Placement::Pinned;
+                }
+                stop_flashing_without_move(flashing_target, operations);
+            }
+            FilterAction::UnpinApp => {
+                if let Placement::AppPinned = placement {
+                    operations.push(PlannedOperation::UnpinApp { window });
+                    *placement = Placement::AtDesktop(current_desktop);
+                }
+                stop_flashing_without_move(flashing_target, operations);
+            }
+            FilterAction::PinApp => {
+                if !matches!(placement, Placement::AppPinned) {
+                    operations.push(PlannedOperation::PinApp { window });
+                    *placement = Placement::AppPinned;
+                }
+                stop_flashing_without_move(flashing_target, operations);
+            }
+            FilterAction::Minimize => operations.push(PlannedOperation::Window {
+                window,
+                command: WindowCommand::Minimize,
+            }),
+            FilterAction::Restore => operations.push(PlannedOperation::Window {
+                window,
+                command: WindowCommand::Restore,
+            }),
+            FilterAction::Maximize => operations.push(PlannedOperation::Window {
+                window,
+                command: WindowCommand::Maximize,
+            }),
+            FilterAction::Close => operations.push(PlannedOperation::Window {
+                window,
+                command: WindowCommand::Close,
+            }),
+            FilterAction::Nothing | FilterAction::Disabled => {}
+        }
+        true
+    }
+
+    /// Find the desktop that a filter with the [`FilterAction::Distribute`]
+    /// action should move its next window to. `current` is the desktop that
+    /// window is at.
+    fn distribute(
+        &mut self,
+        filter_index: usize,
+        filter: &WindowFilter,
+        current: Option<u32>,
+    ) -> Option<u32> {
+        let distribution = filter.distribute_to.unwrap_or_default();
+        let Some(desktops) = distribution.desktop_range(self.context.desktop_count) else {
+            tracing::warn!(
+                ?distribution,
+                desktop_count = ?self.context.desktop_count,
+                "Can't distribute windows since the range of desktops is empty or unknown"
+            );
+            return None;
+        };
+        match distribution.mode {
+            DistributionMode::RoundRobin => {
+                let distributed = self.distributed.entry(filter_index).or_default();
+                let offset = *distributed % (desktops.end() - desktops.start() + 1);
+                *distributed += 1;
+                Some(desktops.start() + offset)
+            }
+            DistributionMode::Balanced => desktops.min_by_key(|&desktop| {
+                let mut count = self.window_counts.get(&desktop).copied().unwrap_or(0);
+                // Ignore the window itself so that it stays if that is balanced:
+                if current == Some(desktop) {
+                    count = count.saturating_sub(1);
+                }
+                (count, current != Some(desktop))
+            }),
+        }
+    }
+}
+
+/// Find the desktop that a filter moves windows to, planning to create it if
+/// needed.
+fn resolve_target(
+    filter: &WindowFilter,
+    context: &mut FilterContext,
+    operations: &mut Vec<PlannedOperation>,
+) -> Option<u32> {
+    match filter.resolve_target_desktop(context) {
+        ResolvedTarget::Index(target) => Some(target),
+        ResolvedTarget::Create { name } => {
+            let is_new = name.is_none();
+            let target = plan_create_desktop(context, operations, |_| name)?;
+            if is_new {
+                context.new_desktop = Some(target);
+            }
+            Some(target)
+        }
+        ResolvedTarget::CreateUntil { index, options } => loop {
+            let created = plan_create_desktop(context, operations, |new| options.name_for(new))?;
+            if created >= index {
+                break Some(index);
+            }
+        },
+        ResolvedTarget::Skip => None,
+    }
+}
+
+/// Plan to create a desktop and return the index it will have. The desktop is
+/// named using `get_name` which is given the index of the new desktop.
+fn plan_create_desktop(
+    context: &mut FilterContext,
+    operations: &mut Vec<PlannedOperation>,
+    get_name: impl FnOnce(u32) -> Option<Arc<str>>,
+) -> Option<u32> {
+    let Some(index) = context.desktop_count else {
+        tracing::warn!("Can't create a virtual desktop since the number of desktops is unknown");
+        return None;
+    };
+    let name = get_name(index);
+    context.add_created_desktop(index, name.clone());
+    operations.push(PlannedOperation::CreateDesktop { index, name });
+    Some(index)
+}
+
+/// What [`execute_plan`] did. Windows are referred to by their index in
+/// [`FilterPlan::windows`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutedPlan {
+    /// Indexes and names of the virtual desktops that were created.
+    pub created_desktops: Vec<(u32, Option<Arc<str>>)>,
+    /// Windows that were moved by filters that requested a notification,
+    /// together with the desktop they were moved to, see
+    /// [`WindowFilter::notify_on_move`].
+    pub notify_moved: Vec<(usize, u32)>,
+    /// Windows and the index of a filter that was successfully applied to
+    /// them, see [`WindowFilter::only_first_time`].
+    pub applied_filters: Vec<(usize, usize)>,
+}
+
+/// Perform the operations in a plan created by [`plan_filters`]. Operations
+/// that fail are logged and skipped.
+pub fn execute_plan<C: WindowController>(plan: &FilterPlan, controller: &mut C) -> ExecutedPlan {
+    let mut result = ExecutedPlan::default();
+    let mut windows_to_prevent_flashing = Vec::new();
+    // Windows that failed to be unpinned shouldn't be moved, and windows that
+    // weren't moved shouldn't be focused or mentioned in notifications:
+    let mut not_moved = Vec::new();
+    // Desktops that couldn't be created. Later desktops would get different
+    // indexes than planned, so they aren't created either:
+    let mut failed_desktops = Vec::new();
+    // Windows where some operation failed, so that the filters that were
+    // applied to them shouldn't be remembered:
+    let mut failed_windows = Vec::new();
+    let mut applied_filters = Vec::new();
+    // Notifications are sent once it is known that the window was moved:
+    let mut notify_moved = Vec::new();
+    // Windows that should be moved when they stop flashing:
+    let mut moved_while_flashing = Vec::new();
+    // Focus windows after they have been moved:
+    let mut windows_to_focus = Vec::new();
+
+    for operation in &plan.operations {
+        match *operation {
+            PlannedOperation::CreateDesktop { index, ref name } => {
+                if !failed_desktops.is_empty() {
+                    failed_desktops.push(index);
+                    continue;
+                }
+                if let Err(e) = controller.create_desktop(index, name.as_deref()) {
+                    tracing::warn!(error = ?e, "Failed to create target desktop");
+                    failed_desktops.push(index);
+                    continue;
+                }
+                result.created_desktops.push((index, name.clone()));
+            }
+            PlannedOperation::Move { window, target } => {
+                if failed_desktops.contains(&target) {
+                    not_moved.push(window);
+                    continue;
+                }
+                if not_moved.contains(&window) {
+                    continue;
+                }
+                if let Err(e) = controller.move_to_desktop(&plan.windows[window], target) {
+                    tracing::warn!(error = ?e, "Failed to move window to target desktop");
+                    not_moved.push(window);
+                    failed_windows.push(window);
+                }
+            }
+            PlannedOperation::Unpin { window } => {
+                if let Err(e) = controller.unpin_window(&plan.windows[window]) {
+                    tracing::warn!(error = ?e, "Failed to unpin window");
+                    not_moved.push(window);
+                }
+            }
+            PlannedOperation::Pin { window } => {
+                if let Err(e) = controller.pin_window(&plan.windows[window]) {
+                    tracing::warn!(error = ?e, "Failed to pin window");
+                    failed_windows.push(window);
+                }
+            }
+            PlannedOperation::UnpinApp { window } => {
+                if let Err(e) = controller.unpin_app(&plan.windows[window]) {
+                    tracing::warn!(error = ?e, "Failed to unpin app");
+                    failed_windows.push(window);
+                }
+            }
+            PlannedOperation::PinApp { window } => {
+                if let Err(e) = controller.pin_app(&plan.windows[window]) {
+                    tracing::warn!(error = ?e, "Failed to pin app");
+                    failed_windows.push(window);
+                }
+            }
+            PlannedOperation::Window {
+                window,
+                command: WindowCommand::Focus,
+            } => windows_to_focus.push(window),
+            PlannedOperation::Window { window, command } => {
+                if let Err(e) = controller.run_command(&plan.windows[window], command) {
+                    tracing::warn!(error = ?e, %command, "Failed to run command for window");
+                    failed_windows.push(window);
+                }
+            }
+            PlannedOperation::Notify { window, target } => notify_moved.push((window, target)),
+            PlannedOperation::StopFlashing { window, mut target } => {
+                if target.is_some_and(|target| failed_desktops.contains(&target)) {
+                    // Still stop the flashing but leave the window where it is:
+                    not_moved.push(window);
+                    target = None;
+                } else if target.is_some() && not_moved.contains(&window) {
+                    continue;
+                }
+                if target.is_some() {
+                    moved_while_flashing.push(window);
+                }
+                windows_to_prevent_flashing.push((&plan.windows[window], target));
+            }
+            PlannedOperation::FilterApplied { window, filter } => {
+                // Operations for the window might come later, for example
+                // when it is moved while it stops flashing:
+                applied_filters.push((window, filter));
+            }
+            PlannedOperation::SkippedAppPinned { .. }
+            | PlannedOperation::SkippedExcluded { .. } => {}
+        }
+    }
+
+    if !windows_to_prevent_flashing.is_empty() {
+        if let Err(e) = controller.stop_flashing(&windows_to_prevent_flashing) {
+            tracing::error!(error = ?e, "Failed to prevent windows from flashing");
+            // Some of the windows might not have been moved:
+            not_moved.extend(&moved_while_flashing);
+            failed_windows.extend(moved_while_flashing);
+        }
+    }
+    result.applied_filters = applied_filters
+        .into_iter()
+        .filter(|(window, _)| !failed_windows.contains(window) && !not_moved.contains(window))
+        .collect();
+    result.notify_moved = notify_moved
+        .into_iter()
+        .filter(|(window, _)| !not_moved.contains(window))
+        .collect();
+    for window in windows_to_focus {
+        if not_moved.contains(&window) {
+            continue;
+        }
+        if let Err(e) = controller.run_command(&plan.windows[window], WindowCommand::Focus) {
+            tracing::warn!(error = ?e, "Failed to focus window");
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        window_filter::{Distribution, DistributionMode, IntegerRange, SeenWindows, TextPattern},
+        window_info::{VirtualDesktopInfo::*, WindowHandle},
+    };
+    use std::time::Instant;
+
+    fn filter(process_name: &str, action: FilterAction, target_desktop: i64) -> WindowFilter {
+        WindowFilter {
+            process_name: TextPattern::new(Arc::from(process_name)),
+            action,
+            target_desktop,
+            ..Default::default()
+        }
+    }
+
+    fn context() -> FilterContext {
+        FilterContext {
+            current_desktop: Some(0),
+            desktop_count: Some(3),
+            ..Default::default()
+        }
+    }
+
+    fn plan_with(
+        filters: Vec<WindowFilter>,
+        windows: Vec<WindowInfo>,
+        stop_flashing: bool,
+        stop_flashing_globally: bool,
+    ) -> Vec<PlannedOperation> {
+        let matcher = FilterMatcher::new(filters.into());
+        plan_filters(
+            Some(&matcher),
+            windows,
+            context(),
+            stop_flashing,
+            stop_flashing_globally,
+        )
+        .operations
+    }
+
+    fn plan(filters: Vec<WindowFilter>, windows: Vec<WindowInfo>) -> Vec<PlannedOperation> {
+        plan_with(filters, windows, false, false)
+    }
+
+    #[test]
+    fn move_to_target_desktop() {
+        let operations = plan(
+            vec![filter("notepad", FilterAction::Move, 2)],
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(2, "notepad", "", AtDesktop { index: 2 }),
+                WindowInfo::for_test(3, "notepad", "", WindowPinned),
+                WindowInfo::for_test(4, "explorer", "", AtDesktop { index: 1 }),
+            ],
+        );
+        assert_eq!(
+            operations,
+            [PlannedOperation::Move {
+                window: 0,
+                target: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn pin_and_unpin() {
+        let windows = || {
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(2, "notepad", "", WindowPinned),
+            ]
+        };
+        assert_eq!(
+            plan(vec![filter("notepad", FilterAction::Pin, 0)], windows()),
+            [PlannedOperation::Pin { window: 0 }]
+        );
+        assert_eq!(
+            plan(vec![filter("notepad", FilterAction::Unpin, 0)], windows()),
+            [PlannedOperation::Unpin { window: 1 }]
+        );
+    }
+
+    #[test]
+    fn unpin_and_move_leaves_unpinned_windows_at_current_desktop() {
+        let operations = plan(
+            vec![filter("notepad", FilterAction::UnpinAndMove, 2)],
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(2, "notepad", "", WindowPinned),
+            ],
+        );
+        assert_eq!(
+            operations,
+            [
+                PlannedOperation::Move {
+                    window: 0,
+                    target: 2
+                },
+                PlannedOperation::Unpin { window: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn close_window() {
+        let operations = plan(
+            vec![filter("notepad", FilterAction::Close, 0)],
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(2, "explorer", "", AtDesktop { index: 1 }),
+            ],
+        );
+        assert_eq!(
+            operations,
+            [PlannedOperation::Window {
+                window: 0,
+                command: WindowCommand::Close
+            }]
+        );
+    }
+
+    #[test]
+    fn stop_flashing_affected_windows() {
+        let operations = plan_with(
+            vec![
+                filter("notepad", FilterAction::Move, 2),
+                filter("explorer", FilterAction::Pin, 0),
+            ],
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(2, "explorer", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(3, "cmd", "", AtDesktop { index: 1 }),
+            ],
+            true,
+            false,
+        );
+        assert_eq!(
+            operations,
+            [
+                PlannedOperation::StopFlashing {
+                    window: 0,
+                    target: Some(2)
+                },
+                PlannedOperation::Pin { window: 1 },
+                PlannedOperation::StopFlashing {
+                    window: 1,
+                    target: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_flashing_all_windows() {
+        let operations = plan_with(
+            vec![filter("notepad", FilterAction::Move, 2)],
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(2, "cmd", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(3, "cmd", "", WindowPinned),
+            ],
+            false,
+            true,
+        );
+        assert_eq!(
+            operations,
+            [
+                PlannedOperation::StopFlashing {
+                    window: 0,
+                    target: Some(2)
+                },
+                PlannedOperation::StopFlashing {
+                    window: 1,
+                    target: Some(1)
+                },
+                PlannedOperation::StopFlashing {
+                    window: 2,
+                    target: None
+                },
+            ]
+        );
+    }
+
+    fn distribute(process_name: &str, mode: DistributionMode) -> WindowFilter {
+        WindowFilter {
+            distribute_to: Some(Distribution {
+                desktops: IntegerRange::default(),
+                mode,
+            }),
+            ..filter(process_name, FilterAction::Distribute, 0)
+        }
+    }
+
+    #[test]
+    fn distribute_round_robin() {
+        // Each filter keeps its own count:
+        let operations = plan(
+            vec![
+                distribute("notepad", DistributionMode::RoundRobin),
+                distribute("explorer", DistributionMode::RoundRobin),
+            ],
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(2, "explorer", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(3, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(4, "explorer", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(5, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(6, "notepad", "", AtDesktop { index: 0 }),
+            ],
+        );
+        assert_eq!(
+            operations,
+            [
+                PlannedOperation::Move {
+                    window: 2,
+                    target: 1
+                },
+                PlannedOperation::Move {
+                    window: 3,
+                    target: 1
+                },
+                PlannedOperation::Move {
+                    window: 4,
+                    target: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn distribute_to_desktop_with_least_windows() {
+        let operations = plan(
+            vec![distribute("notepad", DistributionMode::Balanced)],
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(2, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(3, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(4, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(5, "explorer", "", AtDesktop { index: 1 }),
+                WindowInfo::for_test(6, "notepad", "", AtDesktop { index: 2 }),
+            ],
+        );
+        // Desktop 1 already has a window that isn't distributed and windows
+        // stay if moving them wouldn't improve the balance:
+        assert_eq!(
+            operations,
+            [
+                PlannedOperation::Move {
+                    window: 0,
+                    target: 1
+                },
+                PlannedOperation::Move {
+                    window: 1,
+                    target: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn remember_only_first_time_filters_that_acted() {
+        let filters = vec![WindowFilter {
+            only_first_time: true,
+            ..filter("notepad", FilterAction::Move, 2)
+        }];
+        let windows = vec![
+            WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 0 }),
+            // Pinned windows aren't moved:
+            WindowInfo::for_test(2, "notepad", "", WindowPinned),
+        ];
+        let operations = plan(filters.clone(), windows.clone());
+        assert_eq!(
+            operations,
+            [
+                PlannedOperation::Move {
+                    window: 0,
+                    target: 2
+                },
+                PlannedOperation::FilterApplied {
+                    window: 0,
+                    filter: 0
+                },
+            ]
+        );
+
+        let mut seen = SeenWindows::default();
+        seen.observe_all(&windows, Instant::now());
+        seen.mark_filter_applied(0, WindowHandle(1), 1);
+        let matcher = FilterMatcher::new(filters.into());
+        let plan = plan_filters(
+            Some(&matcher),
+            windows,
+            context().with_seen_windows(Arc::new(seen)),
+            false,
+            false,
+        );
+        assert_eq!(plan.operations, []);
+    }
+
+    #[test]
+    fn unpin_app_pinned_window() {
+        let filters = vec![WindowFilter {
+            apply_to_app_pinned: true,
+            ..filter("notepad", FilterAction::UnpinAndMove, 2)
+        }];
+        let second = WindowInfo {
+            process_id: 1,
+            ..WindowInfo::for_test(2, "notepad", "", AppPinned)
+        };
+        let operations = plan(
+            filters,
+            vec![WindowInfo::for_test(1, "notepad", "", AppPinned), second],
+        );
+        // The app's other windows are at the current desktop once the app is
+        // unpinned, so they can be moved:
+        assert_eq!(
+            operations,
+            [
+                PlannedOperation::UnpinApp { window: 0 },
+                PlannedOperation::Move {
+                    window: 1,
+                    target: 2
+                },
+            ]
+        );
+    }
+
+    /// Records the operations it is asked to perform and fails to move some
+    /// windows.
+    #[derive(Default)]
+    struct TestController {
+        fail_moves_for: Vec<isize>,
+        performed: Vec<String>,
+    }
+    impl TestController {
+        fn record(&mut self, window: &WindowInfo, operation: &str) -> Result<(), ()> {
+            self.performed
+                .push(format!("{operation} {}", window.handle.0));
+            Ok(())
+        }
+    }
+    impl WindowController for TestController {
+        type Error = ();
+
+        fn run_command(&mut self, window: &WindowInfo, command: WindowCommand) -> Result<(), ()> {
+            self.record(window, &command.to_string())
+        }
+        fn create_desktop(&mut self, index: u32, _name: Option<&str>) -> Result<(), ()> {
+            self.performed.push(format!("CreateDesktop {index}"));
+            Ok(())
+        }
+        fn move_to_desktop(&mut self, window: &WindowInfo, _desktop: u32) -> Result<(), ()> {
+            if self.fail_moves_for.contains(&window.handle.0) {
+                return Err(());
+            }
+            self.record(window, "Move")
+        }
+        fn pin_window(&mut self, window: &WindowInfo) -> Result<(), ()> {
+            self.record(window, "Pin")
+        }
+        fn unpin_window(&mut self, window: &WindowInfo) -> Result<(), ()> {
+            self.record(window, "Unpin")
+        }
+        fn pin_app(&mut self, window: &WindowInfo) -> Result<(), ()> {
+            self.record(window, "PinApp")
+        }
+        fn unpin_app(&mut self, window: &WindowInfo) -> Result<(), ()> {
+            self.record(window, "UnpinApp")
+        }
+        fn stop_flashing(&mut self, windows: &[(&WindowInfo, Option<u32>)]) -> Result<(), ()> {
+            for (window, _) in windows {
+                self.record(window, "StopFlashing")?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_move_is_not_notified_or_focused() {
+        let filters = vec![WindowFilter {
+            only_first_time: true,
+            focus_after_move: true,
+            notify_on_move: true,
+            ..filter("notepad", FilterAction::Move, 2)
+        }];
+        let matcher = FilterMatcher::new(filters.into());
+        let plan = plan_filters(
+            Some(&matcher),
+            vec![
+                WindowInfo::for_test(1, "notepad", "", AtDesktop { index: 0 }),
+                WindowInfo::for_test(2, "notepad", "", AtDesktop { index: 1 }),
+            ],
+            context(),
+            false,
+            false,
+        );
+        let mut controller = TestController {
+            fail_moves_for: vec![1],
+            ..Default::default()
+        };
+        let executed = execute_plan(&plan, &mut controller);
+        assert_eq!(controller.performed, ["Move 2", "Focus 2"]);
+        assert_eq!(
+            executed,
+            ExecutedPlan {
+                created_desktops: vec![],
+                notify_moved: vec![(1, 2)],
+                applied_filters: vec![(1, 0)],
+            }
+        );
+    }
+}
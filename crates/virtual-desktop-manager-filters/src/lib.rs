@@ -0,0 +1,13 @@
+//! Rules or "filters" that decide what should happen to windows, together with
+//! everything needed to check them.
+//!
+//! Nothing here calls into Windows APIs, so filters can be checked and planned
+//! for hand-built [`WindowInfo`](window_info::WindowInfo) values on any
+//! platform. The core crate gathers the real window information and executes
+//! the resulting [`FilterPlan`](filter_plan::FilterPlan).
+
+pub mod filter_lint;
+pub mod filter_matcher;
+pub mod filter_plan;
+pub mod window_filter;
+pub mod window_info;
@@ -0,0 +1,3462 @@
+//! Rules or "filters" that specify actions this program should take on all
+//! currently open windows.
+
+#[cfg(feature = "persist_filters_xml")]
+mod xml_format {
+    //! Serialize or deserialize window filters as XML in the format expected by
+    //! the C# program <https://github.com/Lej77/VirtualDesktopManager>.
+    //!
+    //! The types were initially generated by
+    //! <https://github.com/Thomblin/xml_schema_generator>.
+    use serde::{Deserialize, Serialize};
+    use std::borrow::Cow;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct SaveFile {
+        #[serde(rename = "@xmlns:xsi", default)]
+        pub xmlns_xsi: Cow<'static, str>,
+        #[serde(rename = "@xmlns:xsd", default)]
+        pub xmlns_xsd: Cow<'static, str>,
+        pub filters: Filters,
+    }
+    impl Default for SaveFile {
+        fn default() -> Self {
+            Self {
+                xmlns_xsi: Cow::Borrowed("http://www.w3.org/2001/XMLSchema-instance"),
+                xmlns_xsd: Cow::Borrowed("http://www.w3.org/2001/XMLSchema"),
+                filters: Filters::default(),
+            }
+        }
+    }
+
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct Filters {
+        #[serde(rename = "SaveData", default)]
+        pub save_data: Vec<SaveData>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct SaveData {
+        pub data: Data,
+    }
+
+    fn minus_one() -> i64 {
+        -1
+    }
+    /// Handle xml deserializing for empty elements (these are interpreted as
+    /// empty strings by `quick-xml`). This returns `-1` for missing elements.
+    ///
+    /// See <https://github.com/tafia/quick-xml/issues/497> for more info about
+    /// why this is needed.
+    fn deserialize_missing<'de, D>(de: D) -> Result<i64, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let text = <&str>::deserialize(de)?;
+        if text.is_empty() {
+            Ok(-1)
+        } else {
+            text.parse::<i64>().map_err(serde::de::Error::custom)
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Data {
+        /// `-1` when disabled, zero-based index
+        #[serde(
+            rename = "indexLowerBound",
+            default = "minus_one",
+            deserialize_with = "deserialize_missing"
+        )]
+        pub index_lower_bound: i64,
+        /// `-1` when disabled, zero-based index
+        #[serde(
+            rename = "indexUpperBound",
+            default = "minus_one",
+            deserialize_with = "deserialize_missing"
+        )]
+        pub index_upper_bound: i64,
+        /// `-1` when disabled, zero-based index
+        #[serde(
+            rename = "desktopLowerBound",
+            default = "minus_one",
+            deserialize_with = "deserialize_missing"
+        )]
+        pub desktop_lower_bound: i64,
+        /// `-1` when disabled, zero-based index
+        #[serde(
+            rename = "desktopUpperBound",
+            default = "minus_one",
+            deserialize_with = "deserialize_missing"
+        )]
+        pub desktop_upper_bound: i64,
+        #[serde(default)]
+        pub title: Title,
+        #[serde(default)]
+        pub process: Process,
+        #[serde(rename = "isMainProcessWindow")]
+        pub is_main_process_window: bool,
+        #[serde(rename = "checkIfMainWindow", default)]
+        pub check_if_main_window: bool,
+        /// Can be omitted in later versions. `-1` when disabled, zero-based index
+        #[serde(
+            rename = "desktopTarget",
+            default = "minus_one",
+            deserialize_with = "deserialize_missing"
+        )]
+        pub desktop_target: i64,
+        #[serde(rename = "desktopTargetAdv", default)]
+        pub desktop_target_adv: Option<DesktopTargetAdv>,
+    }
+
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct Title {
+        #[serde(default)]
+        pub string: Vec<String>,
+    }
+
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct Process {
+        #[serde(default)]
+        pub string: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DesktopTargetAdv {
+        #[serde(rename = "allowUnpin", default)]
+        pub allow_unpin: bool,
+        #[serde(rename = "shouldPin", default)]
+        pub should_pin: bool,
+        /// `-1` when disabled (not moving to target desktop)
+        #[serde(
+            rename = "targetDesktopIndex",
+            default = "minus_one",
+            deserialize_with = "deserialize_missing"
+        )]
+        pub target_desktop_index: i64,
+    }
+}
+
+#[cfg(feature = "persist_filters")]
+use serde::{Deserialize, Serialize};
+
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt,
+    ops::RangeInclusive,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::window_info::{VirtualDesktopInfo, WindowHandle, WindowInfo};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct IntegerRange {
+    pub lower_bound: Option<i64>,
+    pub upper_bound: Option<i64>,
+}
+impl IntegerRange {
+    pub fn contains(&self, value: i64) -> bool {
+        match (self.lower_bound, self.upper_bound) {
+            (None, None) => true,
+            (None, Some(upper)) => value <= upper,
+            (Some(lower), None) => lower <= value,
+            (Some(lower), Some(upper)) => lower <= value && value <= upper,
+        }
+    }
+    /// A range that only contains values that are in both `self` and `other`.
+    pub fn intersection(self, other: Self) -> Self {
+        fn combine(a: Option<i64>, b: Option<i64>, f: fn(i64, i64) -> i64) -> Option<i64> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(f(a, b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Self {
+            lower_bound: combine(self.lower_bound, other.lower_bound, i64::max),
+            upper_bound: combine(self.upper_bound, other.upper_bound, i64::min),
+        }
+    }
+    /// `true` if every value in `other` is also in `self`.
+    pub fn contains_range(&self, other: Self) -> bool {
+        let lower = match (self.lower_bound, other.lower_bound) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => a <= b,
+        };
+        let upper = match (self.upper_bound, other.upper_bound) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => b <= a,
+        };
+        lower && upper
+    }
+    /// `true` if the range can't contain any value since its lower bound is
+    /// larger than its upper bound.
+    pub fn is_inverted(&self) -> bool {
+        matches!((self.lower_bound, self.upper_bound), (Some(lower), Some(upper)) if lower > upper)
+    }
+    /// Increment both lower and upper bounds in order to convert from
+    /// zero-based to one-based indexes.
+    pub fn into_one_based_indexes(self) -> Self {
+        Self {
+            lower_bound: self.lower_bound.map(|v| v.saturating_add(1)),
+            upper_bound: self.upper_bound.map(|v| v.saturating_add(1)),
+        }
+    }
+    /// Decrement both lower and upper bounds in order to convert from
+    /// one-based to zero-based indexes.
+    pub fn from_one_based_indexes(self) -> Self {
+        Self {
+            lower_bound: self.lower_bound.map(|v| v.saturating_sub(1)),
+            upper_bound: self.upper_bound.map(|v| v.saturating_sub(1)),
+        }
+    }
+}
+/// Used to display range in list column.
+impl fmt::Display for IntegerRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.lower_bound, self.upper_bound) {
+            (None, None) => Ok(()),
+            (None, Some(upper)) => write!(f, "- {upper}"),
+            (Some(lower), None) => write!(f, "{lower} -"),
+            (Some(lower), Some(upper)) => write!(f, "{lower} - {upper}"),
+        }
+    }
+}
+
+/// A set of integers, or all integers except those in a set, for conditions
+/// that can't be expressed as a single [`IntegerRange`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct IntegerSet {
+    pub values: Vec<i64>,
+    /// Contain every value except the ones in [`Self::values`].
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub negated: bool,
+}
+impl IntegerSet {
+    /// Create a set with sorted values and without duplicates.
+    pub fn new(values: impl IntoIterator<Item = i64>, negated: bool) -> Self {
+        let mut values = values.into_iter().collect::<Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+        Self { values, negated }
+    }
+    pub fn contains(&self, value: i64) -> bool {
+        self.values.contains(&value) != self.negated
+    }
+    /// `true` if the set can't contain any value.
+    pub fn is_empty(&self) -> bool {
+        !self.negated && self.values.is_empty()
+    }
+    /// A range that contains exactly the same values as this set, if there is
+    /// one.
+    pub fn as_range(&self) -> Option<IntegerRange> {
+        if self.negated {
+            return self.values.is_empty().then(IntegerRange::default);
+        }
+        let lower = *self.values.iter().min()?;
+        let upper = *self.values.iter().max()?;
+        let distinct = self.values.iter().collect::<HashSet<_>>().len() as u64;
+        (upper.abs_diff(lower) == distinct - 1).then_some(IntegerRange {
+            lower_bound: Some(lower),
+            upper_bound: Some(upper),
+        })
+    }
+    /// Increment all values in order to convert from zero-based to one-based
+    /// indexes.
+    pub fn into_one_based_indexes(self) -> Self {
+        Self {
+            values: self.values.iter().map(|v| v.saturating_add(1)).collect(),
+            ..self
+        }
+    }
+    /// Decrement all values in order to convert from one-based to zero-based
+    /// indexes.
+    pub fn from_one_based_indexes(self) -> Self {
+        Self {
+            values: self.values.iter().map(|v| v.saturating_sub(1)).collect(),
+            ..self
+        }
+    }
+}
+/// Used to display set in list column.
+impl fmt::Display for IntegerSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.values.is_empty() {
+            return if self.negated {
+                Ok(())
+            } else {
+                write!(f, "none")
+            };
+        }
+        if self.negated {
+            write!(f, "not ")?;
+        }
+        for (ix, value) in self.values.iter().enumerate() {
+            if ix > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Specifies how the text of a [`TextPattern`] should be interpreted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum TextMatchMode {
+    /// Each line of the pattern must occur in the text in the same order.
+    /// Anything is allowed where the pattern has a newline, see
+    /// [`TextPattern::check_newline_glob`].
+    #[default]
+    NewlineGlob,
+    /// The text must be exactly equal to the pattern.
+    Exact,
+    /// The pattern is a regular expression that should match somewhere in the
+    /// text. Use `^` and `$` to anchor it to the start or end of the text.
+    Regex,
+}
+impl TextMatchMode {
+    pub fn all() -> [TextMatchMode; 3] {
+        [Self::NewlineGlob, Self::Exact, Self::Regex]
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextMatchMode::NewlineGlob => "Newline glob",
+            TextMatchMode::Exact => "Exact text",
+            TextMatchMode::Regex => "Regex",
+        }
+    }
+}
+/// Used to represent the match mode inside the configuration window.
+impl fmt::Display for TextMatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Unicode normalization that is applied to text before it is compared to a
+/// [`TextPattern`], see <https://unicode.org/reports/tr15/>.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum TextNormalization {
+    /// Compare the text as is.
+    #[default]
+    None,
+    /// Canonical composition, so that for example an accented letter written
+    /// as a single character matches the same letter written with a combining
+    /// accent.
+    Nfc,
+    /// Compatibility composition, which additionally treats things like
+    /// full-width letters and ligatures as their plain equivalents.
+    Nfkc,
+}
+impl TextNormalization {
+    pub fn all() -> [TextNormalization; 3] {
+        [Self::None, Self::Nfc, Self::Nfkc]
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextNormalization::None => "None",
+            TextNormalization::Nfc => "NFC",
+            TextNormalization::Nfkc => "NFKC",
+        }
+    }
+}
+impl fmt::Display for TextNormalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Options that affect how text is compared to a [`TextPattern`]. They are
+/// applied to both the pattern and the checked text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextMatchOptions {
+    /// Ignore the difference between upper and lower case letters.
+    pub ignore_case: bool,
+    pub normalization: TextNormalization,
+    /// Treat every sequence of whitespace characters as a single space.
+    pub collapse_whitespace: bool,
+}
+impl TextMatchOptions {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+    /// Apply these options to some text so that it can be compared to other
+    /// text that was prepared in the same way.
+    pub fn prepare<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        match self.normalization {
+            TextNormalization::None => {}
+            #[cfg(feature = "text_normalization")]
+            TextNormalization::Nfc => {
+                use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+                if is_nfc_quick(text.chars()) != IsNormalized::Yes {
+                    text = Cow::Owned(text.nfc().collect());
+                }
+            }
+            #[cfg(feature = "text_normalization")]
+            TextNormalization::Nfkc => {
+                use unicode_normalization::{is_nfkc_quick, IsNormalized, UnicodeNormalization};
+                if is_nfkc_quick(text.chars()) != IsNormalized::Yes {
+                    text = Cow::Owned(text.nfkc().collect());
+                }
+            }
+            // Warned about when the pattern is compiled:
+            #[cfg(not(feature = "text_normalization"))]
+            TextNormalization::Nfc | TextNormalization::Nfkc => {}
+        }
+        if self.collapse_whitespace {
+            let collapsed = match collapse_whitespace(&text) {
+                Cow::Owned(collapsed) => Some(collapsed),
+                Cow::Borrowed(_) => None,
+            };
+            if let Some(collapsed) = collapsed {
+                text = Cow::Owned(collapsed);
+            }
+        }
+        if self.ignore_case && text.chars().any(|c| !c.to_lowercase().eq([c])) {
+            text = Cow::Owned(text.to_lowercase());
+        }
+        text
+    }
+}
+/// Replace every sequence of whitespace characters with a single space.
+fn collapse_whitespace(text: &str) -> Cow<'_, str> {
+    let mut prev_was_space = false;
+    let needs_change = text.chars().any(|c| {
+        let change = c.is_whitespace() && (prev_was_space || c != ' ');
+        prev_was_space = c.is_whitespace();
+        change
+    });
+    if !needs_change {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut prev_was_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !prev_was_space {
+                result.push(' ');
+            }
+            prev_was_space = true;
+        } else {
+            result.push(c);
+            prev_was_space = false;
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// A [`TextPattern`] prepared for fast matching.
+#[derive(Debug)]
+enum CompiledPattern {
+    /// Fast path for a glob that allows any text.
+    MatchAll,
+    NewlineGlob(Box<[Box<str>]>),
+    Exact(Box<str>),
+    #[cfg(feature = "filter_regex")]
+    Regex(regex::Regex),
+    /// The pattern couldn't be compiled, so it will never match anything.
+    Invalid(Arc<str>),
+}
+
+/// Literal text that must be part of any text that matches a [`TextPattern`],
+/// see [`TextPattern::required_literal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequiredLiteral<'a> {
+    /// The text must be exactly equal to this.
+    Exact(&'a str),
+    /// The text must start with this.
+    Prefix(&'a str),
+    /// The text must end with this.
+    Suffix(&'a str),
+}
+
+/// Lazily compiled version of a [`TextPattern`] that is shared between all
+/// clones of the pattern. This is ignored when comparing patterns.
+#[derive(Default, Clone)]
+struct PatternCache(Arc<OnceLock<CompiledPattern>>);
+impl fmt::Debug for PatternCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PatternCache")
+            .field(&self.0.get().is_some())
+            .finish()
+    }
+}
+impl PartialEq for PatternCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+impl Eq for PatternCache {}
+impl PartialOrd for PatternCache {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for PatternCache {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+/// Serialized form of a [`TextPattern`]. Patterns that only use the default
+/// options are stored as a plain string to remain compatible with older
+/// versions of this program.
+#[cfg(feature = "persist_filters")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TextPatternData {
+    Glob(Arc<str>),
+    WithMode {
+        pattern: Arc<str>,
+        #[serde(default)]
+        mode: TextMatchMode,
+        #[serde(default, skip_serializing_if = "is_false")]
+        ignore_case: bool,
+        #[serde(default, skip_serializing_if = "is_default")]
+        normalization: TextNormalization,
+        #[serde(default, skip_serializing_if = "is_false")]
+        collapse_whitespace: bool,
+    },
+}
+#[cfg(feature = "persist_filters")]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+#[cfg(feature = "persist_filters")]
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+#[cfg(feature = "persist_filters")]
+impl From<TextPatternData> for TextPattern {
+    fn from(value: TextPatternData) -> Self {
+        match value {
+            TextPatternData::Glob(pattern) => Self::new(pattern),
+            TextPatternData::WithMode {
+                pattern,
+                mode,
+                ignore_case,
+                normalization,
+                collapse_whitespace,
+            } => Self::new(pattern)
+                .with_mode(mode)
+                .with_options(TextMatchOptions {
+                    ignore_case,
+                    normalization,
+                    collapse_whitespace,
+                }),
+        }
+    }
+}
+#[cfg(feature = "persist_filters")]
+impl From<TextPattern> for TextPatternData {
+    fn from(value: TextPattern) -> Self {
+        if value.is_plain_glob() {
+            Self::Glob(value.pattern)
+        } else {
+            Self::WithMode {
+                pattern: value.pattern,
+                mode: value.mode,
+                ignore_case: value.options.ignore_case,
+                normalization: value.options.normalization,
+                collapse_whitespace: value.options.collapse_whitespace,
+            }
+        }
+    }
+}
+
+/// Represents a pattern that can be matched against some text.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "persist_filters",
+    serde(from = "TextPatternData", into = "TextPatternData")
+)]
+pub struct TextPattern {
+    pattern: Arc<str>,
+    mode: TextMatchMode,
+    options: TextMatchOptions,
+    compiled: PatternCache,
+}
+impl TextPattern {
+    pub fn new(pattern: Arc<str>) -> Self {
+        Self {
+            pattern,
+            mode: TextMatchMode::default(),
+            options: TextMatchOptions::default(),
+            compiled: PatternCache::default(),
+        }
+    }
+    /// Change how the pattern text is interpreted.
+    pub fn with_mode(self, mode: TextMatchMode) -> Self {
+        Self {
+            mode,
+            compiled: PatternCache::default(),
+            ..self
+        }
+    }
+    /// Change how text is compared to the pattern.
+    pub fn with_options(self, options: TextMatchOptions) -> Self {
+        Self {
+            options,
+            compiled: PatternCache::default(),
+            ..self
+        }
+    }
+    /// Create a new pattern with different text but the same match mode and
+    /// options.
+    pub fn with_pattern(&self, pattern: Arc<str>) -> Self {
+        Self::new(pattern)
+            .with_mode(self.mode)
+            .with_options(self.options)
+    }
+    pub fn mode(&self) -> TextMatchMode {
+        self.mode
+    }
+    pub fn options(&self) -> TextMatchOptions {
+        self.options
+    }
+    /// `true` if the pattern uses the default mode and options, which means
+    /// it is exported as a plain string that older versions of this program
+    /// understand.
+    pub fn is_plain_glob(&self) -> bool {
+        self.mode == TextMatchMode::default() && self.options.is_default()
+    }
+    fn compiled(&self) -> &CompiledPattern {
+        self.compiled.0.get_or_init(|| self.compile())
+    }
+    fn compile(&self) -> CompiledPattern {
+        #[cfg(not(feature = "text_normalization"))]
+        if self.options.normalization != TextNormalization::None {
+            tracing::warn!(
+                pattern = ?self.pattern,
+                "This program was compiled without the \"text_normalization\" feature, \
+                so text in this pattern won't be normalized"
+            );
+        }
+        match self.mode {
+            TextMatchMode::NewlineGlob => {
+                if &*self.pattern == "\n" || &*self.pattern == "\r\n" {
+                    CompiledPattern::MatchAll
+                } else {
+                    CompiledPattern::NewlineGlob(
+                        self.split_newline_glob()
+                            .map(|part| Box::from(self.options.prepare(part)))
+                            .collect(),
+                    )
+                }
+            }
+            TextMatchMode::Exact => {
+                CompiledPattern::Exact(Box::from(self.options.prepare(&self.pattern)))
+            }
+            // Only normalize the expression, other options are handled by the
+            // regex engine or when preparing the checked text:
+            #[cfg(feature = "filter_regex")]
+            TextMatchMode::Regex => match regex::RegexBuilder::new(
+                &TextMatchOptions {
+                    normalization: self.options.normalization,
+                    ..Default::default()
+                }
+                .prepare(&self.pattern),
+            )
+            .case_insensitive(self.options.ignore_case)
+            .build()
+            {
+                Ok(regex) => CompiledPattern::Regex(regex),
+                Err(e) => {
+                    tracing::warn!(pattern = ?self.pattern, error = %e, "Invalid regular expression in filter");
+                    CompiledPattern::Invalid(Arc::from(e.to_string()))
+                }
+            },
+            #[cfg(not(feature = "filter_regex"))]
+            TextMatchMode::Regex => CompiledPattern::Invalid(Arc::from(
+                "This program was compiled without support for regular expressions. \
+                Recompile the program from source with the \"filter_regex\" feature \
+                in order to support such patterns.",
+            )),
+        }
+    }
+    /// Returns an error message if the pattern can never match anything, for
+    /// example if it is an invalid regular expression.
+    pub fn error(&self) -> Option<&str> {
+        match self.compiled() {
+            CompiledPattern::Invalid(e) => Some(e),
+            _ => None,
+        }
+    }
+    /// Literal text that any text must have in order to match this pattern,
+    /// after both have been prepared with the pattern's [`TextMatchOptions`].
+    /// `None` if there is no such text, for example for regular expressions.
+    ///
+    /// This can be used to quickly skip patterns that can't match some text,
+    /// see [`FilterMatcher`](crate::filter_matcher::FilterMatcher).
+    pub fn required_literal(&self) -> Option<RequiredLiteral<'_>> {
+        match self.compiled() {
+            CompiledPattern::NewlineGlob(parts) => match &**parts {
+                // A single line must match the whole text:
+                [only] => Some(RequiredLiteral::Exact(only)),
+                [first, ..] if !first.is_empty() => Some(RequiredLiteral::Prefix(first)),
+                [.., last] if !last.is_empty() => Some(RequiredLiteral::Suffix(last)),
+                _ => None,
+            },
+            CompiledPattern::Exact(pattern) => Some(RequiredLiteral::Exact(pattern)),
+            _ => None,
+        }
+    }
+    /// Check if a text matches this pattern using its [`TextMatchMode`] and
+    /// [`TextMatchOptions`].
+    pub fn is_match(&self, text: &str) -> bool {
+        match self.compiled() {
+            CompiledPattern::MatchAll => true,
+            CompiledPattern::NewlineGlob(parts) => Self::check_glob_parts(
+                parts.iter().map(|part| &**part),
+                &self.options.prepare(text),
+            ),
+            CompiledPattern::Exact(pattern) => **pattern == *self.options.prepare(text),
+            #[cfg(feature = "filter_regex")]
+            CompiledPattern::Regex(regex) => regex.is_match(
+                &TextMatchOptions {
+                    ignore_case: false,
+                    ..self.options
+                }
+                .prepare(text),
+            ),
+            CompiledPattern::Invalid(_) => false,
+        }
+    }
+    /// Check if a text matches a "glob" pattern that allows anything at
+    /// newlines.
+    ///
+    /// Note: this ignores the pattern's [`TextMatchMode`] and
+    /// [`TextMatchOptions`], use [`Self::is_match`] to respect them.
+    pub fn check_newline_glob(&self, text: &str) -> bool {
+        if &*self.pattern == "\n" || &*self.pattern == "\r\n" {
+            // Fast path for pattern that allows any prefix and suffix.
+            return true;
+        }
+        Self::check_glob_parts(self.split_newline_glob(), text)
+    }
+    fn check_glob_parts<'a>(
+        mut patterns: impl DoubleEndedIterator<Item = &'a str>,
+        mut text: &str,
+    ) -> bool {
+        // First line must be a prefix of the text:
+        {
+            let first = patterns
+                .next()
+                .expect("even an empty pattern string should have at least one part");
+            if first.is_empty() {
+                // The glob pattern allows anything at the start of the text.
+            } else if let Some(after) = text.strip_prefix(first) {
+                text = after;
+            } else {
+                return false;
+            };
+        }
+        if let Some(last) = patterns.next_back() {
+            if last.is_empty() {
+                // the glob pattern allows anything at the end of the text.
+            } else if let Some(before) = text.strip_suffix(last) {
+                text = before;
+            } else {
+                return false;
+            };
+        } else {
+            // Only a single line, so after the initial prefix there can't be any more text:
+            return text.is_empty();
+        }
+        // Lines in the middle of the pattern can have anything before or after
+        // their pattern text:
+        for pattern in patterns {
+            if let Some((_, after)) = text.split_once(pattern) {
+                text = after;
+            } else {
+                // The line's text didn't exist in the checked text (at least
+                // not after previous line texts):
+                return false;
+            }
+        }
+        // Prefix and suffix existed + middle lines existed as well:
+        true
+    }
+    pub fn split_newline_glob(&self) -> impl DoubleEndedIterator<Item = &'_ str> {
+        self.pattern
+            .split('\n')
+            .map(|line| line.trim_end_matches('\r'))
+    }
+    /// Visualizes a newline delimited glob pattern in a single line.
+    pub fn display_escaped_newline_glob(&self) -> impl fmt::Display + '_ {
+        struct Helper<'a>(&'a TextPattern);
+        impl fmt::Display for Helper<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for (ix, part) in self.0.split_newline_glob().enumerate() {
+                    if ix != 0 {
+                        write!(f, "*")?;
+                    }
+                    write!(f, "\"{}\"", part.replace('\\', "\\\\").replace('"', "\\\""))?;
+                }
+                Ok(())
+            }
+        }
+        Helper(self)
+    }
+    /// Visualizes the pattern in a single line, taking its [`TextMatchMode`]
+    /// and [`TextMatchOptions`] into account.
+    pub fn display_escaped(&self) -> impl fmt::Display + '_ {
+        struct Helper<'a>(&'a TextPattern);
+        impl fmt::Display for Helper<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0.mode {
+                    TextMatchMode::NewlineGlob => {
+                        fmt::Display::fmt(&self.0.display_escaped_newline_glob(), f)
+                    }
+                    TextMatchMode::Exact => write!(
+                        f,
+                        "=\"{}\"",
+                        self.0
+                            .pattern
+                            .replace('\\', "\\\\")
+                            .replace('"', "\\\"")
+                            .replace('\n', "\\n")
+                    ),
+                    TextMatchMode::Regex => write!(f, "/{}/", self.0.pattern.replace('\n', "\\n")),
+                }?;
+                let options = self.0.options;
+                if !options.is_default() {
+                    let flags = [
+                        options.ignore_case.then_some("ignore case"),
+                        (options.normalization != TextNormalization::None)
+                            .then(|| options.normalization.as_str()),
+                        options.collapse_whitespace.then_some("collapse whitespace"),
+                    ];
+                    write!(f, " (")?;
+                    for (ix, flag) in flags.into_iter().flatten().enumerate() {
+                        if ix != 0 {
+                            write!(f, ", ")?;
+                        }
+                        f.write_str(flag)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+        }
+        Helper(self)
+    }
+    pub fn pattern(&self) -> &Arc<str> {
+        &self.pattern
+    }
+    /// `true` if the pattern allows any text, this is the case for the default
+    /// pattern.
+    pub fn is_match_all(&self) -> bool {
+        matches!(self.compiled(), CompiledPattern::MatchAll)
+    }
+    /// The literal text of the pattern if it can only match a single text
+    /// (ignoring [`TextMatchOptions`]), for example a glob without newlines.
+    pub fn exact_text(&self) -> Option<&str> {
+        match self.compiled() {
+            CompiledPattern::Exact(_) => Some(&self.pattern),
+            CompiledPattern::NewlineGlob(parts) if parts.len() == 1 => {
+                Some(self.pattern.trim_end_matches('\r'))
+            }
+            _ => None,
+        }
+    }
+}
+impl Default for TextPattern {
+    fn default() -> Self {
+        Self::new(Arc::from("\n"))
+    }
+}
+impl From<&str> for TextPattern {
+    fn from(value: &str) -> Self {
+        TextPattern::new(Arc::from(value))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct ExportedWindowFilters {
+    pub version: u64,
+    /// Settings for the groups that the exported filters belong to.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub groups: Vec<FilterGroup>,
+    pub filters: Vec<WindowFilter>,
+}
+impl ExportedWindowFilters {
+    /// Version 2 added every [`WindowFilter`] field except
+    /// [`WindowFilter::window_index`], [`WindowFilter::desktop_index`],
+    /// [`WindowFilter::window_title`], [`WindowFilter::process_name`],
+    /// [`WindowFilter::action`] and [`WindowFilter::target_desktop`]. It also
+    /// added filter groups, all actions other than moving, pinning, unpinning
+    /// and doing nothing, and [`TextPattern`]s with a [`TextMatchMode`] or
+    /// [`TextMatchOptions`] other than the default ones.
+    pub const CURRENT_VERSION: u64 = 2;
+
+    /// Prepare filters for export. The lowest version that can represent the
+    /// filters is used so that older versions of this program can still
+    /// import them.
+    pub fn new(filters: Vec<WindowFilter>) -> Self {
+        let filters = filters
+            .into_iter()
+            .map(|filter| filter.flatten_conditions().flatten_index_sets())
+            .collect::<Vec<_>>();
+        Self {
+            version: filters
+                .iter()
+                .map(WindowFilter::required_export_version)
+                .max()
+                .unwrap_or(1),
+            groups: Vec::new(),
+            filters,
+        }
+    }
+    /// Also export settings for the groups that the filters belong to.
+    pub fn with_groups(mut self, groups: &[FilterGroup]) -> Self {
+        self.groups = groups
+            .iter()
+            .filter(|group| self.filters.iter().any(|filter| filter.group == group.name))
+            .cloned()
+            .collect();
+        if !self.groups.is_empty() {
+            self.version = self.version.max(2);
+        }
+        self
+    }
+    /// Prepare the filters in a single group for export.
+    pub fn for_group(filters: &[WindowFilter], groups: &[FilterGroup], group: &str) -> Self {
+        Self::new(FilterGroup::filters_in_group(filters, group)).with_groups(groups)
+    }
+    pub fn migrate_and_get_filters(self) -> Vec<WindowFilter> {
+        self.migrate_and_get_filters_with_groups().0
+    }
+    pub fn migrate_and_get_filters_with_groups(self) -> (Vec<WindowFilter>, Vec<FilterGroup>) {
+        if self.version > Self::CURRENT_VERSION {
+            tracing::warn!(
+                version = self.version,
+                "Filters were exported by a newer version of this program, \
+                some options might be ignored"
+            );
+        }
+        (self.filters, self.groups)
+    }
+}
+impl Default for ExportedWindowFilters {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            groups: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+}
+
+/// Filters converted to the legacy XML format by
+/// [`WindowFilter::serialize_to_xml`].
+#[cfg(feature = "persist_filters_xml")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyXmlExport {
+    pub xml: String,
+    /// Zero-based indexes of filters that use options that can't be
+    /// represented in the legacy format, so they were exported as disabled.
+    pub disabled_filters: Vec<usize>,
+}
+#[cfg(feature = "persist_filters_xml")]
+impl LegacyXmlExport {
+    /// A message that tells the user which filters were exported as disabled,
+    /// using one-based indexing. `None` if no filter was disabled.
+    pub fn warning(&self) -> Option<String> {
+        let (last, rest) = self.disabled_filters.split_last()?;
+        let mut indexes = rest
+            .iter()
+            .map(|index| index.saturating_add(1).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !rest.is_empty() {
+            indexes.push_str(" and ");
+        }
+        indexes.push_str(&last.saturating_add(1).to_string());
+        Some(format!(
+            "{} {indexes} {} options that can't be represented in the legacy XML format, \
+            so {} exported as disabled.",
+            if rest.is_empty() { "Filter" } else { "Filters" },
+            if rest.is_empty() { "uses" } else { "use" },
+            if rest.is_empty() {
+                "it was"
+            } else {
+                "they were"
+            },
+        ))
+    }
+}
+
+/// Settings for a named group of filters, for example a "Work" or "Streaming"
+/// profile. Filters belong to a group if their [`WindowFilter::group`] is the
+/// same as the group's name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct FilterGroup {
+    pub name: Arc<str>,
+    /// Filters in disabled groups are ignored when all filters are applied,
+    /// but the group can still be applied by itself.
+    pub enabled: bool,
+}
+impl FilterGroup {
+    /// `true` if filters in the group with this name should be applied when
+    /// all filters are applied. Filters without a group and groups without
+    /// settings are enabled.
+    pub fn is_enabled(groups: &[Self], name: &str) -> bool {
+        name.is_empty()
+            || groups
+                .iter()
+                .find(|group| &*group.name == name)
+                .is_none_or(|group| group.enabled)
+    }
+    /// All groups that have settings followed by groups that are only used by
+    /// filters.
+    pub fn all_groups(groups: &[Self], filters: &[WindowFilter]) -> Vec<Self> {
+        let mut all = groups.to_vec();
+        for filter in filters {
+            if !filter.group.is_empty() && !all.iter().any(|group| group.name == filter.group) {
+                all.push(Self {
+                    name: filter.group.clone(),
+                    enabled: true,
+                });
+            }
+        }
+        all
+    }
+    /// The filters that should be used when applying all filters.
+    pub fn enabled_filters(groups: &[Self], filters: &[WindowFilter]) -> Vec<WindowFilter> {
+        filters
+            .iter()
+            .filter(|filter| Self::is_enabled(groups, &filter.group))
+            .cloned()
+            .collect()
+    }
+    /// The filters that belong to a specific group, even if that group is
+    /// disabled.
+    pub fn filters_in_group(filters: &[WindowFilter], group: &str) -> Vec<WindowFilter> {
+        filters
+            .iter()
+            .filter(|filter| &*filter.group == group)
+            .cloned()
+            .collect()
+    }
+    /// Like [`Self::enabled_filters`] but filters in disabled groups are kept
+    /// with the [`FilterAction::Disabled`] action. Every filter then keeps its
+    /// index, which identifies it in [`SeenWindows`].
+    pub fn disable_filters_in_disabled_groups(
+        groups: &[Self],
+        filters: &[WindowFilter],
+    ) -> Vec<WindowFilter> {
+        Self::disable_filters_unless(filters, |filter| Self::is_enabled(groups, &filter.group))
+    }
+    /// Like [`Self::filters_in_group`] but filters in other groups are kept
+    /// with the [`FilterAction::Disabled`] action, see
+    /// [`Self::disable_filters_in_disabled_groups`].
+    pub fn disable_filters_outside_group(
+        filters: &[WindowFilter],
+        group: &str,
+    ) -> Vec<WindowFilter> {
+        Self::disable_filters_unless(filters, |filter| &*filter.group == group)
+    }
+    fn disable_filters_unless(
+        filters: &[WindowFilter],
+        keep: impl Fn(&WindowFilter) -> bool,
+    ) -> Vec<WindowFilter> {
+        filters
+            .iter()
+            .map(|filter| {
+                if keep(filter) {
+                    filter.clone()
+                } else {
+                    WindowFilter {
+                        action: FilterAction::Disabled,
+                        ..filter.clone()
+                    }
+                }
+            })
+            .collect()
+    }
+    /// Enable or disable a group, adding settings for it if there were none.
+    pub fn set_enabled(groups: &[Self], name: &str, enabled: bool) -> Arc<[Self]> {
+        let mut groups = groups.to_vec();
+        if let Some(group) = groups.iter_mut().find(|group| &*group.name == name) {
+            group.enabled = enabled;
+        } else {
+            groups.push(Self {
+                name: Arc::from(name),
+                enabled,
+            });
+        }
+        Arc::from(groups)
+    }
+    /// Add settings for imported groups unless there are already settings for
+    /// a group with the same name.
+    pub fn merge(groups: &[Self], imported: impl IntoIterator<Item = Self>) -> Arc<[Self]> {
+        let mut merged = groups.to_vec();
+        for group in imported {
+            if !merged.iter().any(|existing| existing.name == group.name) {
+                merged.push(group);
+            }
+        }
+        Arc::from(merged)
+    }
+}
+
+/// Windows that this program should never touch, no matter which filters
+/// match them. Useful to protect programs like `explorer` or a VPN client from
+/// catch-all filters.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "persist_filters", serde(default))]
+pub struct WindowExclusions {
+    /// Windows owned by a process whose name matches any of these patterns
+    /// are excluded.
+    pub process_names: Vec<TextPattern>,
+    /// Windows whose title matches any of these patterns are excluded.
+    pub window_titles: Vec<TextPattern>,
+}
+impl WindowExclusions {
+    pub fn is_empty(&self) -> bool {
+        self.process_names.is_empty() && self.window_titles.is_empty()
+    }
+    /// `true` if the window must be left alone.
+    ///
+    /// An invalid pattern (for example a regular expression when this program
+    /// was compiled without regex support) excludes every window since it is
+    /// unknown which windows it was meant to protect.
+    pub fn is_excluded(&self, window: &WindowInfo) -> bool {
+        let excludes =
+            |pattern: &TextPattern, text: &str| pattern.error().is_some() || pattern.is_match(text);
+        self.process_names
+            .iter()
+            .any(|pattern| excludes(pattern, &window.process_name))
+            || self
+                .window_titles
+                .iter()
+                .any(|pattern| excludes(pattern, &window.title))
+    }
+    /// Pattern for a process name that was entered in the configuration
+    /// window. It must equal the whole process name, ignoring case.
+    pub fn process_name_pattern(text: &str) -> TextPattern {
+        TextPattern::new(Arc::from(text))
+            .with_mode(TextMatchMode::Exact)
+            .with_options(TextMatchOptions {
+                ignore_case: true,
+                ..Default::default()
+            })
+    }
+    /// Pattern for a window title that was entered in the configuration
+    /// window. It is a regular expression that ignores case.
+    pub fn window_title_pattern(text: &str) -> TextPattern {
+        TextPattern::new(Arc::from(text))
+            .with_mode(TextMatchMode::Regex)
+            .with_options(TextMatchOptions {
+                ignore_case: true,
+                ..Default::default()
+            })
+    }
+    /// Parse text with one pattern per line, as shown in the configuration
+    /// window. Patterns in `previous` that have the same text are reused so
+    /// that match modes and options that were set in the settings file are
+    /// kept.
+    pub fn patterns_from_lines(
+        text: &str,
+        previous: &[TextPattern],
+        new_pattern: fn(&str) -> TextPattern,
+    ) -> Vec<TextPattern> {
+        text.lines()
+            .map(|line| line.trim_matches('\r'))
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                previous
+                    .iter()
+                    .find(|pattern| **pattern.pattern() == *line)
+                    .cloned()
+                    .unwrap_or_else(|| new_pattern(line))
+            })
+            .collect()
+    }
+    /// Write patterns with one pattern per line, see
+    /// [`Self::patterns_from_lines`].
+    pub fn patterns_to_lines(patterns: &[TextPattern]) -> String {
+        patterns
+            .iter()
+            .map(|pattern| &**pattern.pattern())
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+}
+
+/// The position of each window among the windows of its process, used by
+/// [`FilterCondition::ProcessWindowIndex`] and
+/// [`FilterCondition::ProcessWindowCount`].
+#[derive(Debug, Default, Clone)]
+pub struct ProcessWindows {
+    /// Zero-based index of each window among the windows of its process.
+    indexes: HashMap<(WindowHandle, u32), u32>,
+    /// The number of windows that each process has.
+    counts: HashMap<u32, u32>,
+}
+impl ProcessWindows {
+    /// Windows should be ordered like they are for
+    /// [`WindowFilter::window_index`].
+    pub fn from_windows(windows: &[WindowInfo]) -> Self {
+        let mut this = Self::default();
+        for window in windows {
+            this.add(window);
+        }
+        this
+    }
+    /// Add a window after all windows that were added earlier. Does nothing
+    /// if the window was already added.
+    pub fn add(&mut self, window: &WindowInfo) {
+        let Entry::Vacant(entry) = self.indexes.entry((window.handle, window.process_id)) else {
+            return;
+        };
+        let count = self.counts.entry(window.process_id).or_default();
+        entry.insert(*count);
+        *count += 1;
+    }
+    /// Zero-based index of the window among the windows of its process, so
+    /// the most recently used window of a process has index 0.
+    pub fn index_of(&self, window: &WindowInfo) -> Option<u32> {
+        self.indexes
+            .get(&(window.handle, window.process_id))
+            .copied()
+    }
+    /// The number of windows that the window's process has.
+    pub fn count_for(&self, window: &WindowInfo) -> Option<u32> {
+        self.index_of(window)?;
+        self.counts.get(&window.process_id).copied()
+    }
+}
+
+/// Windows that were open when filters were applied, used by
+/// [`WindowFilter::only_first_time`] and [`WindowFilter::settle_delay_ms`].
+/// Windows are identified by both their handle and process id since Windows
+/// reuses handles and process ids once windows are closed.
+#[derive(Debug, Default, Clone)]
+pub struct SeenWindows {
+    windows: HashMap<(WindowHandle, u32), SeenWindow>,
+    /// When the windows were last observed.
+    observed_at: Option<Instant>,
+}
+#[derive(Debug, Clone)]
+struct SeenWindow {
+    title: String,
+    /// When the window was first seen or its title last changed.
+    changed_at: Instant,
+    /// When filters were last applied to the window.
+    applied_at: Option<Instant>,
+    /// Indexes of the filters whose actions were successfully applied to the
+    /// window.
+    applied_filters: HashSet<usize>,
+}
+impl SeenWindows {
+    /// Record the currently open windows. Windows that aren't in the list
+    /// have been closed and are forgotten.
+    pub fn observe_all(&mut self, windows: &[WindowInfo], now: Instant) {
+        let open = windows
+            .iter()
+            .map(|window| (window.handle, window.process_id))
+            .collect::<HashSet<_>>();
+        self.windows.retain(|key, _| open.contains(key));
+        for window in windows {
+            self.observe(window.handle, window.process_id, &window.title, now);
+        }
+        self.observed_at = Some(now);
+    }
+    /// Record a single window. Returns `true` if the window is new or its
+    /// title changed.
+    pub fn observe(
+        &mut self,
+        handle: WindowHandle,
+        process_id: u32,
+        title: &str,
+        now: Instant,
+    ) -> bool {
+        match self.windows.entry((handle, process_id)) {
+            Entry::Occupied(mut entry) => {
+                let seen = entry.get_mut();
+                if seen.title == title {
+                    return false;
+                }
+                seen.title = title.to_owned();
+                seen.changed_at = now;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(SeenWindow {
+                    title: title.to_owned(),
+                    changed_at: now,
+                    applied_at: None,
+                    applied_filters: HashSet::new(),
+                });
+            }
+        }
+        true
+    }
+    /// Remember that filters were checked against the windows that were last
+    /// observed by [`Self::observe_all`], see [`Self::applied_since_settled`].
+    pub fn mark_applied(&mut self) {
+        let Some(now) = self.observed_at else {
+            return;
+        };
+        for seen in self.windows.values_mut() {
+            seen.applied_at = Some(now);
+        }
+    }
+    /// Remember that the action of the filter with index `filter_index` was
+    /// successfully applied to a window.
+    pub fn mark_filter_applied(
+        &mut self,
+        filter_index: usize,
+        handle: WindowHandle,
+        process_id: u32,
+    ) {
+        if let Some(seen) = self.windows.get_mut(&(handle, process_id)) {
+            seen.applied_filters.insert(filter_index);
+        }
+    }
+    /// Update the filter indexes that were recorded by
+    /// [`Self::mark_filter_applied`] after the filter list changed from `old`
+    /// to `new`. Filters are matched regardless of their action so that
+    /// lists where some filters were disabled or replaced by "Nothing" keep
+    /// their records. Records of removed filters are forgotten.
+    pub fn remap_filters(&mut self, old: &[WindowFilter], new: &[WindowFilter]) {
+        let same_filter = |old: &WindowFilter, new: &WindowFilter| {
+            WindowFilter {
+                action: new.action,
+                ..old.clone()
+            } == *new
+        };
+        let mut taken = vec![false; new.len()];
+        let mut mapping = HashMap::new();
+        for (old_ix, old) in old.iter().enumerate() {
+            let preferred = (old_ix < new.len()).then_some(old_ix);
+            let new_ix = preferred
+                .into_iter()
+                .chain(0..new.len())
+                .find(|&ix| !taken[ix] && same_filter(old, &new[ix]));
+            if let Some(new_ix) = new_ix {
+                taken[new_ix] = true;
+                mapping.insert(old_ix, new_ix);
+            }
+        }
+        for seen in self.windows.values_mut() {
+            seen.applied_filters = seen
+                .applied_filters
+                .iter()
+                .filter_map(|ix| mapping.get(ix).copied())
+                .collect();
+        }
+    }
+    /// How long the window's title has been unchanged, `None` if the window
+    /// hasn't been observed.
+    pub fn settled_for(&self, window: &WindowInfo) -> Option<Duration> {
+        let seen = self.windows.get(&(window.handle, window.process_id))?;
+        Some(self.observed_at?.saturating_duration_since(seen.changed_at))
+    }
+    /// `true` if the action of the filter with index `filter_index` was
+    /// applied to the window, see [`Self::mark_filter_applied`].
+    pub fn was_applied(&self, filter_index: usize, window: &WindowInfo) -> bool {
+        self.windows
+            .get(&(window.handle, window.process_id))
+            .is_some_and(|seen| seen.applied_filters.contains(&filter_index))
+    }
+    /// `true` if filters were last applied to the window after its current
+    /// title had been unchanged for at least `settle_delay`.
+    pub fn applied_since_settled(&self, window: &WindowInfo, settle_delay: Duration) -> bool {
+        let Some(seen) = self.windows.get(&(window.handle, window.process_id)) else {
+            return false;
+        };
+        seen.applied_at.is_some_and(|applied_at| {
+            applied_at >= seen.changed_at
+                && applied_at.duration_since(seen.changed_at) >= settle_delay
+        })
+    }
+}
+
+/// The result of [`WindowFilter::find_actions_among`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FoundActions<'a> {
+    /// Filters whose actions should be applied to the window, together with
+    /// their indexes.
+    pub actions: Vec<(usize, &'a WindowFilter)>,
+    /// A matching filter is waiting for the window to settle, so the window
+    /// should be checked again after this delay.
+    pub recheck_after: Option<Duration>,
+}
+
+/// Displays an index range together with an optional index set using
+/// one-based indexing.
+struct IndexesFmt<'a>(IntegerRange, Option<&'a IntegerSet>);
+impl fmt::Display for IndexesFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = self.0.into_one_based_indexes().to_string();
+        let set = self
+            .1
+            .map(|set| set.clone().into_one_based_indexes().to_string())
+            .unwrap_or_default();
+        match (range.is_empty(), set.is_empty()) {
+            (false, false) => write!(f, "{range}; {set}"),
+            _ => write!(f, "{range}{set}"),
+        }
+    }
+}
+
+/// Specifies how to filter all windows to select a subset and also what action
+/// should be applied to the selected windows.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct WindowFilter {
+    /// A name that describes what the filter is used for.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "str::is_empty")
+    )]
+    pub name: Arc<str>,
+    /// Labels that can be used to find related filters.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub tags: Vec<Arc<str>>,
+    /// The name of the [`FilterGroup`] this filter belongs to. Empty if the
+    /// filter isn't part of any group.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "str::is_empty")
+    )]
+    pub group: Arc<str>,
+    /// This index is lower if the window was recently accessed. Limiting this
+    /// index is therefore a way to only affect recently used windows.
+    ///
+    /// Note: uses zero-based indexing.
+    pub window_index: IntegerRange,
+    /// The index of the virtual desktop that window should be on. If the window
+    /// is pinned then that is always allowed.
+    ///
+    /// Note: uses zero-based indexing.
+    pub desktop_index: IntegerRange,
+    /// Like [`Self::window_index`] but allows a set of indexes or every index
+    /// except some. Both must contain the window's index if this is used.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub window_index_set: Option<IntegerSet>,
+    /// Like [`Self::desktop_index`] but allows a set of indexes or every index
+    /// except some. Pinned windows are always allowed.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub desktop_index_set: Option<IntegerSet>,
+    /// The text in the title bar of a window.
+    pub window_title: TextPattern,
+    /// The name of the process that created and owns a window.
+    pub process_name: TextPattern,
+    /// The name of the window class that the window was created with, can be
+    /// used to select specific kinds of windows such as dialogs.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "TextPattern::is_match_all")
+    )]
+    pub window_class: TextPattern,
+    /// The full path to the executable of the process that owns a window.
+    /// Useful to distinguish between programs that have the same process name.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "TextPattern::is_match_all")
+    )]
+    pub executable_path: TextPattern,
+    /// Extra conditions that must also be fulfilled by a window. Unlike the
+    /// fields above these can be combined using "any" and "not".
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub conditions: Option<FilterCondition>,
+    /// The action to preform on windows that match all the conditions in this
+    /// filter.
+    pub action: FilterAction,
+    /// If the action specifies that the window should move then this specifies
+    /// the desktop it should be moved to.
+    ///
+    /// Note: uses zero-based indexing.
+    pub target_desktop: i64,
+    /// Resolve the desktop that windows are moved to when the filter is
+    /// applied instead of using [`Self::target_desktop`].
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub dynamic_target: Option<DesktopTarget>,
+    /// Create virtual desktops until [`Self::target_desktop`] exists instead
+    /// of ignoring windows when there are too few desktops.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub create_missing_desktops: Option<CreateDesktops>,
+    /// The desktops that windows are spread across by
+    /// [`FilterAction::Distribute`]. All desktops are used if this is `None`.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub distribute_to: Option<Distribution>,
+    /// Keep evaluating later filters after this one matched a window, so that
+    /// their actions are also applied to it.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub continue_after_match: bool,
+    /// Only apply the action once to each window, so that windows the user
+    /// moved afterwards are left alone when filters are applied again. See
+    /// [`SeenWindows`].
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub only_first_time: bool,
+    /// Wait until a window's title has been unchanged for this many
+    /// milliseconds before applying the filter to it. Useful for programs that
+    /// show a splash screen or a "Loading..." title at first. Such windows are
+    /// checked again once they have settled.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "is_default")
+    )]
+    pub settle_delay_ms: u32,
+    /// Apply the action even to windows whose process has all of its windows
+    /// pinned. Such windows are left alone by default since moving a single
+    /// window doesn't affect the rest of the app. Actions that unpin such a
+    /// window unpin the whole app.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub apply_to_app_pinned: bool,
+    /// Bring windows to the foreground after they have been moved to another
+    /// desktop.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub focus_after_move: bool,
+    /// Stop windows that this filter affects from flashing in the taskbar.
+    /// Overrides the global setting for this filter if it isn't `None`.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub stop_flashing: Option<bool>,
+    /// Show a notification when this filter moves a window to another
+    /// desktop.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub notify_on_move: bool,
+}
+impl WindowFilter {
+    #[cfg(feature = "persist_filters_xml")]
+    pub fn deserialize_from_xml(xml: &str) -> Result<Vec<Self>, Box<dyn std::error::Error>> {
+        let mut deserializer = quick_xml::de::Deserializer::from_str(xml);
+        #[cfg(not(feature = "serde_path_to_error"))]
+        let data: xml_format::SaveFile = Deserialize::deserialize(&mut deserializer)?;
+        #[cfg(feature = "serde_path_to_error")]
+        let data: xml_format::SaveFile = serde_path_to_error::deserialize(&mut deserializer)?;
+
+        Ok(data
+            .filters
+            .save_data
+            .into_iter()
+            .map(|filter| Self {
+                name: Arc::from(""),
+                tags: Vec::new(),
+                group: Arc::from(""),
+                window_index: IntegerRange {
+                    lower_bound: Some(filter.data.index_lower_bound).filter(|&v| v >= 0),
+                    upper_bound: Some(filter.data.index_upper_bound).filter(|&v| v >= 0),
+                },
+                desktop_index: IntegerRange {
+                    lower_bound: Some(filter.data.desktop_lower_bound).filter(|&v| v >= 0),
+                    upper_bound: Some(filter.data.desktop_upper_bound).filter(|&v| v >= 0),
+                },
+                window_index_set: None,
+                desktop_index_set: None,
+                window_title: if filter.data.title.string.is_empty()
+                    || filter.data.title.string == [""]
+                {
+                    TextPattern::default()
+                } else {
+                    TextPattern::new(Arc::from(filter.data.title.string.join("\n")))
+                },
+                process_name: if filter.data.process.string.is_empty() {
+                    TextPattern::default()
+                } else {
+                    TextPattern::new(Arc::from(filter.data.process.string.join("\n")))
+                },
+                window_class: TextPattern::default(),
+                executable_path: TextPattern::default(),
+                conditions: None,
+                action: if let Some(adv) = &filter.data.desktop_target_adv {
+                    if adv.should_pin {
+                        FilterAction::Pin
+                    } else if adv.target_desktop_index >= 0 {
+                        if adv.allow_unpin {
+                            FilterAction::UnpinAndMove
+                        } else {
+                            FilterAction::Move
+                        }
+                    } else if adv.allow_unpin {
+                        FilterAction::Unpin
+                    } else {
+                        FilterAction::Disabled
+                    }
+                } else if filter.data.desktop_target >= 0 {
+                    FilterAction::Move
+                } else {
+                    FilterAction::Disabled
+                },
+                target_desktop: filter
+                    .data
+                    .desktop_target_adv
+                    .as_ref()
+                    .map(|adv| adv.target_desktop_index)
+                    .unwrap_or_else(|| filter.data.desktop_target)
+                    .max(0),
+                dynamic_target: None,
+                create_missing_desktops: None,
+                distribute_to: None,
+                continue_after_match: false,
+                only_first_time: false,
+                settle_delay_ms: 0,
+                apply_to_app_pinned: false,
+                focus_after_move: false,
+                stop_flashing: None,
+                notify_on_move: false,
+            })
+            .collect())
+    }
+    /// Filters that use options that the legacy format can't represent are
+    /// exported as disabled, see [`LegacyXmlExport::disabled_filters`].
+    #[cfg(feature = "persist_filters_xml")]
+    pub fn serialize_to_xml(
+        filters: &[WindowFilter],
+    ) -> Result<LegacyXmlExport, Box<dyn std::error::Error>> {
+        let mut disabled_filters = Vec::new();
+        let filters = filters
+            .iter()
+            .enumerate()
+            .map(|(index, filter)| {
+                // The legacy format has no names, tags or groups so those are
+                // dropped without disabling the filter. It also can't affect
+                // whole apps so such actions only affect the matched window:
+                let filter = WindowFilter {
+                    name: Arc::from(""),
+                    tags: Vec::new(),
+                    group: Arc::from(""),
+                    action: match filter.action {
+                        FilterAction::UnpinApp => FilterAction::Unpin,
+                        FilterAction::PinApp => FilterAction::Pin,
+                        action => action,
+                    },
+                    ..filter.clone()
+                }
+                .flatten_conditions()
+                .flatten_index_sets();
+                let action = if filter.required_export_version() > 1 {
+                    disabled_filters.push(index);
+                    tracing::warn!(
+                        filter_index = index,
+                        filter = ?filter,
+                        "Filter uses options that can't be represented in the legacy XML \
+                        format, exporting the filter as disabled"
+                    );
+                    FilterAction::Disabled
+                } else {
+                    filter.action
+                };
+                (filter, action)
+            })
+            .map(|(filter, action)| xml_format::SaveData {
+                data: xml_format::Data {
+                    index_lower_bound: filter.window_index.lower_bound.unwrap_or(-1),
+                    index_upper_bound: filter.window_index.upper_bound.unwrap_or(-1),
+                    desktop_lower_bound: filter.desktop_index.lower_bound.unwrap_or(-1),
+                    desktop_upper_bound: filter.desktop_index.upper_bound.unwrap_or(-1),
+                    title: xml_format::Title {
+                        string: Self::pattern_to_xml_lines(&filter.window_title),
+                    },
+                    process: xml_format::Process {
+                        string: Self::pattern_to_xml_lines(&filter.process_name),
+                    },
+                    is_main_process_window: false,
+                    check_if_main_window: false,
+                    desktop_target: if matches!(
+                        action,
+                        FilterAction::Move | FilterAction::UnpinAndMove
+                    ) {
+                        filter.target_desktop
+                    } else {
+                        -1
+                    },
+                    desktop_target_adv: Some(xml_format::DesktopTargetAdv {
+                        allow_unpin: matches!(
+                            action,
+                            FilterAction::Unpin | FilterAction::UnpinAndMove
+                        ),
+                        should_pin: matches!(action, FilterAction::Pin),
+                        target_desktop_index: if matches!(
+                            action,
+                            FilterAction::Move | FilterAction::UnpinAndMove | FilterAction::Pin
+                        ) {
+                            filter.target_desktop
+                        } else {
+                            -1
+                        },
+                    }),
+                },
+            })
+            .collect::<Vec<_>>();
+        let xml_data = xml_format::SaveFile {
+            filters: xml_format::Filters { save_data: filters },
+            ..Default::default()
+        };
+        Ok(LegacyXmlExport {
+            xml: quick_xml::se::to_string(&xml_data)?,
+            disabled_filters,
+        })
+    }
+    /// The legacy XML format only supports newline globs, so other patterns
+    /// are exported on a best-effort basis.
+    #[cfg(feature = "persist_filters_xml")]
+    fn pattern_to_xml_lines(pattern: &TextPattern) -> Vec<String> {
+        if !pattern.options().is_default() {
+            tracing::warn!(
+                pattern = ?pattern.pattern(),
+                options = ?pattern.options(),
+                "Text match options can't be represented in the legacy XML format, \
+                exporting the pattern without them"
+            );
+        }
+        match pattern.mode() {
+            TextMatchMode::NewlineGlob => pattern
+                .pattern()
+                .replace('\r', "")
+                .split('\n')
+                .map(String::from)
+                .collect(),
+            // A glob with a single line must match the whole text:
+            TextMatchMode::Exact => vec![pattern.pattern().replace('\r', "")],
+            TextMatchMode::Regex => {
+                tracing::warn!(
+                    pattern = ?pattern.pattern(),
+                    "Regular expressions can't be represented in the legacy XML format, \
+                    exporting the pattern text as an exact match"
+                );
+                vec![pattern.pattern().replace('\r', "")]
+            }
+        }
+    }
+    /// Keep only the effects of filters that bring windows to the current
+    /// desktop. Other filters are kept so that they still stop evaluation for
+    /// the windows they match, but their actions are replaced with
+    /// [`FilterAction::Nothing`].
+    pub fn bring_here_filters(filters: &[Self]) -> Vec<Self> {
+        filters
+            .iter()
+            .map(|filter| match filter.action {
+                action if action.brings_here() || action == FilterAction::Disabled => {
+                    filter.clone()
+                }
+                _ => WindowFilter {
+                    action: FilterAction::Nothing,
+                    ..filter.clone()
+                },
+            })
+            .collect()
+    }
+    /// Find the first filter that applies to a window. Returns `None` if a
+    /// matching filter is still waiting for the window to settle, see
+    /// [`Self::settle_delay_ms`].
+    pub fn find_first_action<'a>(
+        filters: &'a [Self],
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> Option<&'a Self> {
+        Self::find_first_action_among(filters.iter().enumerate(), window_index, window, context)
+    }
+    /// Like [`Self::find_first_action`] but only checks some of the filters.
+    /// The candidates must be ordered by their index.
+    pub fn find_first_action_among<'a>(
+        candidates: impl IntoIterator<Item = (usize, &'a Self)>,
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> Option<&'a Self> {
+        candidates
+            .into_iter()
+            // ignore disabled filters:
+            .filter(|(_, filter)| filter.action != FilterAction::Disabled)
+            // then find first that can be applied:
+            .find(|(ix, filter)| filter.check_filter_at(*ix, window_index, window, context))
+            // later filters must not be used before this one has been applied:
+            .filter(|(_, filter)| filter.remaining_settle_delay(window, context).is_none())
+            .map(|(_, filter)| filter)
+    }
+    /// Find all filters whose actions should be applied to a window, in the
+    /// order they should be applied. Evaluation stops at the first matching
+    /// filter that doesn't have [`Self::continue_after_match`] set, or that
+    /// is still waiting for the window to settle.
+    ///
+    /// Only the first action that changes the window's virtual desktop is
+    /// kept since later ones would contradict it (for example "Pin" followed
+    /// by "Move"). Evaluation also stops after a [`FilterAction::Close`]
+    /// since the window will be gone.
+    pub fn find_actions<'a>(
+        filters: &'a [Self],
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> Vec<&'a Self> {
+        Self::find_actions_among(filters.iter().enumerate(), window_index, window, context)
+            .actions
+            .into_iter()
+            .map(|(_, filter)| filter)
+            .collect()
+    }
+    /// Like [`Self::find_actions`] but only checks some of the filters and
+    /// also returns the index of each filter and how long to wait until the
+    /// window should be checked again. The candidates must be ordered by
+    /// their index.
+    pub fn find_actions_among<'a>(
+        candidates: impl IntoIterator<Item = (usize, &'a Self)>,
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> FoundActions<'a> {
+        let mut actions = Vec::new();
+        let mut has_placement = false;
+        for (ix, filter) in candidates {
+            if filter.action == FilterAction::Disabled
+                || !filter.check_filter_at(ix, window_index, window, context)
+            {
+                continue;
+            }
+            if let Some(delay) = filter.remaining_settle_delay(window, context) {
+                // Later filters must not be applied before this one:
+                return FoundActions {
+                    actions,
+                    recheck_after: Some(delay),
+                };
+            }
+            if filter.action.changes_placement() {
+                if has_placement {
+                    tracing::debug!(
+                        filter_index = ix,
+                        action = ?filter.action,
+                        window_title = window.title,
+                        "Ignored filter action since an earlier filter already \
+                        changed the virtual desktop of the window"
+                    );
+                } else {
+                    has_placement = true;
+                    actions.push((ix, filter));
+                }
+            } else {
+                actions.push((ix, filter));
+            }
+            if !filter.continue_after_match || filter.action == FilterAction::Close {
+                break;
+            }
+        }
+        FoundActions {
+            actions,
+            recheck_after: None,
+        }
+    }
+    /// Display the actions returned by [`Self::find_actions`] in a single
+    /// line.
+    pub fn display_actions<'a>(actions: &'a [&'a Self]) -> impl fmt::Display + 'a {
+        struct Helper<'a>(&'a [&'a WindowFilter]);
+        impl fmt::Display for Helper<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for (ix, filter) in self.0.iter().enumerate() {
+                    if ix != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", filter.display_target_desktop())?;
+                }
+                Ok(())
+            }
+        }
+        Helper(actions)
+    }
+    pub fn settle_delay(&self) -> Duration {
+        Duration::from_millis(self.settle_delay_ms.into())
+    }
+    /// How long to wait until the window has settled enough for the filter to
+    /// be applied to it, see [`Self::settle_delay_ms`]. Delays are ignored for
+    /// windows that haven't been observed, for example when filters are
+    /// applied from the command line.
+    pub fn remaining_settle_delay(
+        &self,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> Option<Duration> {
+        let settled_for = context.seen_windows.settled_for(window)?;
+        Some(self.settle_delay().saturating_sub(settled_for)).filter(|delay| !delay.is_zero())
+    }
+    /// Like [`Self::check_window`] but also skips windows that this filter
+    /// was already applied to if [`Self::only_first_time`] is set. The
+    /// `filter_index` identifies the filter in [`FilterContext::seen_windows`].
+    pub fn check_filter_at(
+        &self,
+        filter_index: usize,
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> bool {
+        if self.only_first_time && context.seen_windows.was_applied(filter_index, window) {
+            return false;
+        }
+        self.check_window(window_index, window, context)
+    }
+    /// Check if this filter/rule applies to a specific widow.
+    ///
+    /// The `window_index` should use zero-based indexing. The filter's
+    /// [`Self::settle_delay_ms`] is not considered, see
+    /// [`Self::remaining_settle_delay`].
+    pub fn check_window(
+        &self,
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> bool {
+        if !self.window_index.contains(i64::from(window_index)) {
+            return false;
+        }
+        if !FilterCondition::check_desktop_index(&self.desktop_index, window) {
+            return false;
+        }
+        if let Some(set) = &self.window_index_set {
+            if !set.contains(i64::from(window_index)) {
+                return false;
+            }
+        }
+        if let (Some(set), VirtualDesktopInfo::AtDesktop { index, .. }) =
+            (&self.desktop_index_set, window.virtual_desktop)
+        {
+            if !set.contains(i64::from(index)) {
+                return false;
+            }
+        }
+        if !self.window_title.is_match(&window.title) {
+            return false;
+        }
+        if !self.process_name.is_match(&window.process_name) {
+            return false;
+        }
+        if !self.window_class.is_match(&window.class_name) {
+            return false;
+        }
+        if !self.executable_path.is_match(&window.executable_path) {
+            return false;
+        }
+        if let Some(conditions) = &self.conditions {
+            if !conditions.check_window(window_index, window, context) {
+                return false;
+            }
+        }
+        true
+    }
+    /// Move as much as possible of [`Self::conditions`] into the simpler
+    /// fields of the filter without changing what windows it matches. The
+    /// `conditions` are removed if they could be fully represented that way.
+    pub fn flatten_conditions(mut self) -> Self {
+        let Some(conditions) = self.conditions.take() else {
+            return self;
+        };
+        let mut remaining = Vec::new();
+        self.absorb_condition(conditions, &mut remaining);
+        self.conditions = match remaining.len() {
+            0 => None,
+            1 => remaining.pop(),
+            _ => Some(FilterCondition::All(remaining)),
+        };
+        self
+    }
+    fn absorb_condition(
+        &mut self,
+        condition: FilterCondition,
+        remaining: &mut Vec<FilterCondition>,
+    ) {
+        match condition {
+            FilterCondition::All(conditions) => {
+                for condition in conditions {
+                    self.absorb_condition(condition, remaining);
+                }
+            }
+            FilterCondition::Any(mut conditions) if conditions.len() == 1 => {
+                self.absorb_condition(conditions.remove(0), remaining);
+            }
+            FilterCondition::Not(inner) => match *inner {
+                FilterCondition::Not(inner) => self.absorb_condition(*inner, remaining),
+                inner => remaining.push(FilterCondition::Not(Box::new(inner))),
+            },
+            FilterCondition::WindowIndex(range) => {
+                self.window_index = self.window_index.intersection(range);
+            }
+            FilterCondition::DesktopIndex(range) => {
+                self.desktop_index = self.desktop_index.intersection(range);
+            }
+            FilterCondition::WindowTitle(pattern) if self.window_title.is_match_all() => {
+                self.window_title = pattern;
+            }
+            FilterCondition::ProcessName(pattern) if self.process_name.is_match_all() => {
+                self.process_name = pattern;
+            }
+            FilterCondition::WindowClass(pattern) if self.window_class.is_match_all() => {
+                self.window_class = pattern;
+            }
+            FilterCondition::ExecutablePath(pattern) if self.executable_path.is_match_all() => {
+                self.executable_path = pattern;
+            }
+            condition => remaining.push(condition),
+        }
+    }
+    /// Replace index sets that contain the same values as a range with that
+    /// range, see [`IntegerSet::as_range`].
+    pub fn flatten_index_sets(mut self) -> Self {
+        for (range, set) in [
+            (&mut self.window_index, &mut self.window_index_set),
+            (&mut self.desktop_index, &mut self.desktop_index_set),
+        ] {
+            if let Some(set_range) = set.as_ref().and_then(IntegerSet::as_range) {
+                *range = range.intersection(set_range);
+                *set = None;
+            }
+        }
+        self
+    }
+    /// The lowest [`ExportedWindowFilters::version`] that can represent this
+    /// filter.
+    pub fn required_export_version(&self) -> u64 {
+        if self.conditions.is_some()
+            || !self.name.is_empty()
+            || !self.tags.is_empty()
+            || !self.group.is_empty()
+            || self.window_index_set.is_some()
+            || self.desktop_index_set.is_some()
+            || self.continue_after_match
+            || self.only_first_time
+            || self.settle_delay_ms != 0
+            || self.apply_to_app_pinned
+            || self.focus_after_move
+            || self.stop_flashing.is_some()
+            || self.notify_on_move
+            || !matches!(
+                self.action,
+                FilterAction::Move
+                    | FilterAction::UnpinAndMove
+                    | FilterAction::Unpin
+                    | FilterAction::Pin
+                    | FilterAction::Nothing
+                    | FilterAction::Disabled
+            )
+            || self.dynamic_target.is_some()
+            || self.create_missing_desktops.is_some()
+            || self.distribute_to.is_some()
+            || !self.window_class.is_match_all()
+            || !self.executable_path.is_match_all()
+            || !self.window_title.is_plain_glob()
+            || !self.process_name.is_plain_glob()
+        {
+            2
+        } else {
+            1
+        }
+    }
+    /// Display the window indexes that the filter allows. (Displays indexes
+    /// with one-based indexing.)
+    pub fn display_window_index(&self) -> impl fmt::Display + '_ {
+        IndexesFmt(self.window_index, self.window_index_set.as_ref())
+    }
+    /// Display the desktop indexes that the filter allows. (Displays indexes
+    /// with one-based indexing.)
+    pub fn display_desktop_index(&self) -> impl fmt::Display + '_ {
+        IndexesFmt(self.desktop_index, self.desktop_index_set.as_ref())
+    }
+    /// Display a short string with information about the action and the
+    /// targeted desktop. (Displays target desktop with one-based indexing.)
+    pub fn display_target_desktop(&self) -> impl fmt::Display + '_ {
+        struct TargetDesktopFmt<'a>(&'a WindowFilter);
+        impl TargetDesktopFmt<'_> {
+            fn fmt_target(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match &self.0.dynamic_target {
+                    Some(target) => write!(f, "{target}"),
+                    None => write!(f, "{}", self.0.target_desktop.saturating_add(1)),
+                }
+            }
+            fn fmt_focus(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                if self.0.focus_after_move {
+                    write!(f, " (Focus)")?;
+                }
+                Ok(())
+            }
+        }
+        impl fmt::Display for TargetDesktopFmt<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0.action {
+                    FilterAction::Move => {
+                        self.fmt_target(f)?;
+                        self.fmt_focus(f)
+                    }
+                    FilterAction::UnpinAndMove => {
+                        self.fmt_target(f)?;
+                        write!(f, " (Unpin)")?;
+                        self.fmt_focus(f)
+                    }
+                    FilterAction::BringHere => {
+                        write!(f, "Here")?;
+                        self.fmt_focus(f)
+                    }
+                    FilterAction::UnpinAndBringHere => {
+                        write!(f, "Here (Unpin)")?;
+                        self.fmt_focus(f)
+                    }
+                    FilterAction::Distribute => {
+                        write!(f, "{}", self.0.distribute_to.unwrap_or_default())?;
+                        self.fmt_focus(f)
+                    }
+                    FilterAction::Unpin => write!(f, "Unpin"),
+                    FilterAction::Pin => write!(f, "Pin"),
+                    FilterAction::UnpinApp => write!(f, "Unpin App"),
+                    FilterAction::PinApp => write!(f, "Pin App"),
+                    FilterAction::Minimize => write!(f, "Minimize"),
+                    FilterAction::Restore => write!(f, "Restore"),
+                    FilterAction::Maximize => write!(f, "Maximize"),
+                    FilterAction::Close => write!(f, "Close"),
+                    FilterAction::Nothing => write!(f, "None"),
+                    FilterAction::Disabled => write!(f, "Disabled"),
+                }
+            }
+        }
+        TargetDesktopFmt(self)
+    }
+    /// Determine which desktop windows should be moved to by this filter.
+    pub fn resolve_target_desktop(&self, context: &FilterContext) -> ResolvedTarget {
+        let target_index = || match u32::try_from(self.target_desktop) {
+            Ok(index) => match context.desktop_count {
+                Some(count) if index >= count => {
+                    if let Some(options) = &self.create_missing_desktops {
+                        ResolvedTarget::CreateUntil {
+                            index,
+                            options: options.clone(),
+                        }
+                    } else {
+                        tracing::warn!(
+                            target_desktop = index,
+                            desktop_count = count,
+                            "Filter targets a desktop that doesn't exist"
+                        );
+                        ResolvedTarget::Skip
+                    }
+                }
+                _ => ResolvedTarget::Index(index),
+            },
+            Err(_) => {
+                tracing::error!(
+                    target_desktop = self.target_desktop,
+                    "Tried to target a desktop outside the range of u32"
+                );
+                ResolvedTarget::Skip
+            }
+        };
+        match &self.dynamic_target {
+            None => target_index(),
+            Some(DesktopTarget::Named { name, if_missing }) => {
+                if let Some(index) = context.find_desktop_by_name(name) {
+                    return ResolvedTarget::Index(index);
+                }
+                match if_missing {
+                    MissingDesktop::Skip => ResolvedTarget::Skip,
+                    MissingDesktop::UseIndex => target_index(),
+                    MissingDesktop::Create => match name.exact_text() {
+                        Some(text) => ResolvedTarget::Create {
+                            name: Some(Arc::from(text)),
+                        },
+                        None => {
+                            tracing::warn!(
+                                pattern = ?name.pattern(),
+                                "Can't create a virtual desktop for a name pattern \
+                                that doesn't specify a single name"
+                            );
+                            ResolvedTarget::Skip
+                        }
+                    },
+                }
+            }
+            Some(DesktopTarget::Current { offset }) => {
+                let Some(current) = context.current_desktop else {
+                    tracing::warn!("Can't move window relative to unknown current desktop");
+                    return ResolvedTarget::Skip;
+                };
+                let mut index = i64::from(current).saturating_add(*offset).max(0);
+                if let Some(count) = context.desktop_count {
+                    index = index.min(i64::from(count).saturating_sub(1));
+                }
+                u32::try_from(index).map_or(ResolvedTarget::Skip, ResolvedTarget::Index)
+            }
+            Some(DesktopTarget::Last) => match context.desktop_count {
+                Some(count @ 1..) => ResolvedTarget::Index(count - 1),
+                _ => {
+                    tracing::warn!(
+                        "Can't move window to last desktop since desktop count is unknown"
+                    );
+                    ResolvedTarget::Skip
+                }
+            },
+            Some(DesktopTarget::New) => match context.new_desktop {
+                Some(index) => ResolvedTarget::Index(index),
+                None => ResolvedTarget::Create { name: None },
+            },
+        }
+    }
+}
+
+/// Information about the virtual desktops and excluded windows that is used
+/// when checking filters.
+#[derive(Debug, Clone, Default)]
+pub struct FilterContext {
+    /// The names of all virtual desktops, ordered by their index. Empty if the
+    /// names aren't known, for example since they aren't supported on
+    /// Windows 10.
+    pub desktop_names: Vec<Arc<str>>,
+    /// Zero-based index of the desktop that is currently shown.
+    pub current_desktop: Option<u32>,
+    /// The number of virtual desktops.
+    pub desktop_count: Option<u32>,
+    /// Zero-based index of the desktop that was created for
+    /// [`DesktopTarget::New`], shared by all filters while they are applied.
+    pub new_desktop: Option<u32>,
+    /// The local time when the filters are applied, used by
+    /// [`FilterCondition::Schedule`]. Schedules are never fulfilled if this is
+    /// unknown.
+    pub local_time: Option<LocalTime>,
+    /// Windows that filters must never affect.
+    pub exclusions: Arc<WindowExclusions>,
+    /// Windows that were seen when filters were applied, used by
+    /// [`WindowFilter::only_first_time`] and
+    /// [`WindowFilter::settle_delay_ms`].
+    pub seen_windows: Arc<SeenWindows>,
+    /// Only check windows that hadn't settled the last time filters were
+    /// applied to them, used when windows are checked again after a
+    /// [`WindowFilter::settle_delay_ms`].
+    pub only_unsettled: bool,
+    /// The windows of each process, calculated by
+    /// [`filter_plan::plan_filters`](crate::filter_plan::plan_filters) from
+    /// the windows it is given.
+    pub process_windows: ProcessWindows,
+}
+impl FilterContext {
+    /// Never let filters affect windows that match these exclusions.
+    pub fn with_exclusions(self, exclusions: Arc<WindowExclusions>) -> Self {
+        Self { exclusions, ..self }
+    }
+    /// Treat these windows as already seen when checking filters.
+    pub fn with_seen_windows(self, seen_windows: Arc<SeenWindows>) -> Self {
+        Self {
+            seen_windows,
+            ..self
+        }
+    }
+    /// Only check windows that hadn't settled when filters were last applied.
+    pub fn with_only_unsettled(self, only_unsettled: bool) -> Self {
+        Self {
+            only_unsettled,
+            ..self
+        }
+    }
+    /// Find the zero-based index of the first desktop whose name matches a
+    /// pattern.
+    pub fn find_desktop_by_name(&self, name: &TextPattern) -> Option<u32> {
+        self.desktop_names
+            .iter()
+            .position(|desktop_name| name.is_match(desktop_name))
+            .and_then(|index| u32::try_from(index).ok())
+    }
+    /// Remember a desktop that was created while applying filters so that
+    /// later windows can be moved to it as well.
+    pub fn add_created_desktop(&mut self, index: u32, name: Option<Arc<str>>) {
+        if let Some(count) = &mut self.desktop_count {
+            *count = (*count).max(index + 1);
+        }
+        if let Some(name) = name {
+            let index = index as usize;
+            if self.desktop_names.len() <= index {
+                self.desktop_names.resize(index + 1, Arc::from(""));
+            }
+            self.desktop_names[index] = name;
+        }
+    }
+}
+
+/// A virtual desktop that is looked up when a [`WindowFilter`] is applied.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum DesktopTarget {
+    /// The first desktop whose name matches a pattern.
+    Named {
+        name: TextPattern,
+        #[cfg_attr(feature = "persist_filters", serde(default))]
+        if_missing: MissingDesktop,
+    },
+    /// The desktop that is currently shown, offset by some number of desktops
+    /// (for example `1` for the next desktop). The result is limited to the
+    /// existing desktops.
+    Current {
+        #[cfg_attr(feature = "persist_filters", serde(default))]
+        offset: i64,
+    },
+    /// The desktop with the highest index.
+    Last,
+    /// A newly created desktop. Only one desktop is created each time filters
+    /// are applied, so all windows are moved to the same new desktop.
+    New,
+}
+impl fmt::Display for DesktopTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DesktopTarget::Named { name, .. } => write!(f, "{}", name.display_escaped()),
+            DesktopTarget::Current { offset: 0 } => write!(f, "Current"),
+            DesktopTarget::Current { offset } => write!(f, "Current{offset:+}"),
+            DesktopTarget::Last => write!(f, "Last"),
+            DesktopTarget::New => write!(f, "New"),
+        }
+    }
+}
+
+/// What to do when a [`DesktopTarget`] doesn't refer to any existing desktop.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum MissingDesktop {
+    /// Don't move the window.
+    #[default]
+    Skip,
+    /// Create a new desktop with the wanted name. Only possible if the name
+    /// pattern matches a single specific name.
+    Create,
+    /// Use [`WindowFilter::target_desktop`] instead.
+    UseIndex,
+}
+
+/// Specifies how [`WindowFilter::create_missing_desktops`] creates desktops.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct CreateDesktops {
+    /// Name of created desktops where `{}` is replaced with the one-based
+    /// index of the desktop. Created desktops aren't named if this is empty.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "str::is_empty")
+    )]
+    pub name_template: Arc<str>,
+}
+impl CreateDesktops {
+    /// The name that a created desktop with a zero-based index should have.
+    pub fn name_for(&self, index: u32) -> Option<Arc<str>> {
+        if self.name_template.is_empty() {
+            return None;
+        }
+        Some(Arc::from(
+            self.name_template
+                .replace("{}", &index.saturating_add(1).to_string()),
+        ))
+    }
+}
+
+/// Specifies how [`FilterAction::Distribute`] spreads windows across desktops.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct Distribution {
+    /// Zero-based indexes of the desktops that windows are moved to. Without
+    /// an upper bound windows are spread until the last desktop.
+    #[cfg_attr(feature = "persist_filters", serde(default))]
+    pub desktops: IntegerRange,
+    #[cfg_attr(feature = "persist_filters", serde(default))]
+    pub mode: DistributionMode,
+}
+impl Distribution {
+    /// The zero-based indexes of the existing desktops that windows should be
+    /// spread across. `None` if there are no such desktops or if they are
+    /// unknown.
+    pub fn desktop_range(&self, desktop_count: Option<u32>) -> Option<RangeInclusive<u32>> {
+        let first = self.desktops.lower_bound.unwrap_or(0).max(0);
+        let last = match (self.desktops.upper_bound, desktop_count) {
+            (Some(upper), Some(count)) => upper.min(i64::from(count) - 1),
+            (Some(upper), None) => upper,
+            (None, Some(count)) => i64::from(count) - 1,
+            (None, None) => return None,
+        };
+        if first > last {
+            return None;
+        }
+        Some(u32::try_from(first).ok()?..=u32::try_from(last).ok()?)
+    }
+}
+/// Displays the range of desktops using one-based indexing.
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = self.desktops.into_one_based_indexes();
+        write!(f, "Spread ")?;
+        match (range.lower_bound, range.upper_bound) {
+            (None, None) => write!(f, "all")?,
+            (lower, upper) => {
+                write!(f, "{}-", lower.unwrap_or(1))?;
+                if let Some(upper) = upper {
+                    write!(f, "{upper}")?;
+                }
+            }
+        }
+        match self.mode {
+            DistributionMode::RoundRobin => Ok(()),
+            DistributionMode::Balanced => write!(f, " (Balanced)"),
+        }
+    }
+}
+
+/// How [`FilterAction::Distribute`] picks the desktop for each window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum DistributionMode {
+    /// Move matching windows to each desktop in turn, ordered by their window
+    /// index.
+    #[default]
+    RoundRobin,
+    /// Move each matching window to the desktop that has the fewest windows,
+    /// counting windows that don't match the filter as well. Windows stay at
+    /// their current desktop if no other desktop has fewer windows.
+    Balanced,
+}
+
+/// The result of [`WindowFilter::resolve_target_desktop`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedTarget {
+    /// Move to the desktop with this zero-based index.
+    Index(u32),
+    /// Create a new desktop, optionally with a name, and then move to it. The
+    /// name is `None` for [`DesktopTarget::New`].
+    Create { name: Option<Arc<str>> },
+    /// Create desktops until there is one with this zero-based index and then
+    /// move to it.
+    CreateUntil { index: u32, options: CreateDesktops },
+    /// Don't move the window.
+    Skip,
+}
+
+/// How a window is placed on the virtual desktops, see
+/// [`FilterCondition::PinState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum PinState {
+    /// The window is only shown on a single virtual desktop.
+    AtDesktop,
+    /// The window is shown on all virtual desktops.
+    WindowPinned,
+    /// All windows of the window's process are shown on all virtual desktops.
+    AppPinned,
+}
+impl PinState {
+    pub fn of(window: &WindowInfo) -> Self {
+        match window.virtual_desktop {
+            VirtualDesktopInfo::AtDesktop { .. } => Self::AtDesktop,
+            VirtualDesktopInfo::WindowPinned => Self::WindowPinned,
+            VirtualDesktopInfo::AppPinned => Self::AppPinned,
+        }
+    }
+}
+
+/// A day of the week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+impl Weekday {
+    pub const ALL: &'static [Self] = &[
+        Self::Monday,
+        Self::Tuesday,
+        Self::Wednesday,
+        Self::Thursday,
+        Self::Friday,
+        Self::Saturday,
+        Self::Sunday,
+    ];
+    /// Convert from the number of days since Sunday, as used by the Windows
+    /// API.
+    pub fn from_days_since_sunday(days: u16) -> Self {
+        Self::ALL[(usize::from(days) + 6) % 7]
+    }
+    pub fn previous(self) -> Self {
+        Self::ALL[(self as usize + 6) % 7]
+    }
+}
+
+/// A time of day with minute precision. Serialized as text such as `"09:30"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "persist_filters",
+    derive(Serialize, Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct TimeOfDay {
+    /// Minutes since midnight.
+    minutes: u16,
+}
+impl TimeOfDay {
+    /// Midnight at the start of a day.
+    pub const MIDNIGHT: Self = Self { minutes: 0 };
+
+    /// Returns `None` if the hour or minute is out of range.
+    pub const fn new(hour: u8, minute: u8) -> Option<Self> {
+        if hour >= 24 || minute >= 60 {
+            return None;
+        }
+        Some(Self {
+            minutes: hour as u16 * 60 + minute as u16,
+        })
+    }
+    pub const fn hour(self) -> u8 {
+        (self.minutes / 60) as u8
+    }
+    pub const fn minute(self) -> u8 {
+        (self.minutes % 60) as u8
+    }
+}
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour(), self.minute())
+    }
+}
+impl std::str::FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid time of day \"{s}\", expected a time such as \"09:30\"");
+        let (hour, minute) = s.trim().split_once(':').ok_or_else(error)?;
+        let hour = hour.parse().map_err(|_| error())?;
+        let minute = minute.parse().map_err(|_| error())?;
+        Self::new(hour, minute).ok_or_else(error)
+    }
+}
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+impl From<TimeOfDay> for String {
+    fn from(value: TimeOfDay) -> Self {
+        value.to_string()
+    }
+}
+
+/// A point in time that a [`Schedule`] can be checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    pub weekday: Weekday,
+    pub time: TimeOfDay,
+}
+
+/// Limits when a filter applies, for example "between 09:00 and 17:00 on
+/// weekdays".
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub struct Schedule {
+    /// The days when the schedule is active. Every day if this is empty.
+    #[cfg_attr(
+        feature = "persist_filters",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub days: Vec<Weekday>,
+    /// The schedule is active from this time.
+    #[cfg_attr(feature = "persist_filters", serde(default))]
+    pub start: TimeOfDay,
+    /// The schedule is active until (but not including) this time. If this is
+    /// before [`Self::start`] then the schedule continues past midnight into
+    /// the next day, and if it is the same as the start then the schedule is
+    /// active the whole day.
+    #[cfg_attr(feature = "persist_filters", serde(default))]
+    pub end: TimeOfDay,
+}
+impl Schedule {
+    /// Check if the schedule is active at a specific time. If the schedule
+    /// continues past midnight then the time after midnight belongs to the
+    /// previous day.
+    pub fn contains(&self, now: LocalTime) -> bool {
+        let has_day = |day: Weekday| self.days.is_empty() || self.days.contains(&day);
+        match self.start.cmp(&self.end) {
+            Ordering::Equal => has_day(now.weekday),
+            Ordering::Less => self.start <= now.time && now.time < self.end && has_day(now.weekday),
+            Ordering::Greater => {
+                if self.start <= now.time {
+                    has_day(now.weekday)
+                } else {
+                    now.time < self.end && has_day(now.weekday.previous())
+                }
+            }
+        }
+    }
+}
+
+/// A condition that a window must fulfill for a [`WindowFilter`] to apply to
+/// it. Conditions can be nested to combine them in different ways.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum FilterCondition {
+    /// Every inner condition must be fulfilled. Always fulfilled if empty.
+    All(Vec<FilterCondition>),
+    /// At least one inner condition must be fulfilled. Never fulfilled if
+    /// empty.
+    Any(Vec<FilterCondition>),
+    /// The inner condition must not be fulfilled.
+    Not(Box<FilterCondition>),
+    /// Same as [`WindowFilter::window_index`].
+    WindowIndex(IntegerRange),
+    /// Same as [`WindowFilter::desktop_index`], so pinned windows always
+    /// fulfill this condition.
+    DesktopIndex(IntegerRange),
+    /// Same as [`WindowFilter::window_title`].
+    WindowTitle(TextPattern),
+    /// Same as [`WindowFilter::process_name`].
+    ProcessName(TextPattern),
+    /// Same as [`WindowFilter::window_class`].
+    WindowClass(TextPattern),
+    /// Same as [`WindowFilter::executable_path`].
+    ExecutablePath(TextPattern),
+    /// Like [`Self::DesktopIndex`] but the bounds are specified as desktop
+    /// names that are looked up when the filter is checked. Not fulfilled if
+    /// a bound doesn't match any desktop.
+    NamedDesktopRange {
+        #[cfg_attr(feature = "persist_filters", serde(default))]
+        first: Option<TextPattern>,
+        #[cfg_attr(feature = "persist_filters", serde(default))]
+        last: Option<TextPattern>,
+    },
+    /// Zero-based index of the window among the windows of its process, for
+    /// example to select the second window of a browser. Windows are ordered
+    /// like for [`Self::WindowIndex`]. Never fulfilled if the windows of the
+    /// process aren't known, see [`FilterContext::process_windows`].
+    ProcessWindowIndex(IntegerRange),
+    /// The number of windows that the window's process has. Never fulfilled
+    /// if the windows of the process aren't known, see
+    /// [`FilterContext::process_windows`].
+    ProcessWindowCount(IntegerRange),
+    /// Fulfilled if the window is at a virtual desktop whose name matches the
+    /// pattern. Unlike [`Self::NamedDesktopRange`] this doesn't depend on the
+    /// order of the desktops. Never fulfilled for pinned windows or if the
+    /// desktop names aren't known, see [`FilterContext::desktop_names`].
+    DesktopName(TextPattern),
+    /// Only fulfilled at certain times, see [`FilterContext::local_time`].
+    Schedule(Schedule),
+    /// Fulfilled if a window is pinned in a specific way. Unlike
+    /// [`Self::DesktopIndex`] this can be used to select or exclude pinned
+    /// windows.
+    PinState(PinState),
+}
+impl FilterCondition {
+    /// Check if a window fulfills this condition.
+    ///
+    /// The `window_index` should use zero-based indexing.
+    pub fn check_window(
+        &self,
+        window_index: i32,
+        window: &WindowInfo,
+        context: &FilterContext,
+    ) -> bool {
+        match self {
+            FilterCondition::All(conditions) => conditions
+                .iter()
+                .all(|condition| condition.check_window(window_index, window, context)),
+            FilterCondition::Any(conditions) => conditions
+                .iter()
+                .any(|condition| condition.check_window(window_index, window, context)),
+            FilterCondition::Not(condition) => {
+                !condition.check_window(window_index, window, context)
+            }
+            FilterCondition::WindowIndex(range) => range.contains(i64::from(window_index)),
+            FilterCondition::DesktopIndex(range) => Self::check_desktop_index(range, window),
+            FilterCondition::WindowTitle(pattern) => pattern.is_match(&window.title),
+            FilterCondition::ProcessName(pattern) => pattern.is_match(&window.process_name),
+            FilterCondition::WindowClass(pattern) => pattern.is_match(&window.class_name),
+            FilterCondition::ExecutablePath(pattern) => pattern.is_match(&window.executable_path),
+            FilterCondition::NamedDesktopRange { first, last } => {
+                let mut range = IntegerRange::default();
+                for (bound, name) in [
+                    (&mut range.lower_bound, first),
+                    (&mut range.upper_bound, last),
+                ] {
+                    if let Some(name) = name {
+                        let Some(index) = context.find_desktop_by_name(name) else {
+                            return false;
+                        };
+                        *bound = Some(i64::from(index));
+                    }
+                }
+                Self::check_desktop_index(&range, window)
+            }
+            FilterCondition::ProcessWindowIndex(range) => context
+                .process_windows
+                .index_of(window)
+                .is_some_and(|index| range.contains(i64::from(index))),
+            FilterCondition::ProcessWindowCount(range) => context
+                .process_windows
+                .count_for(window)
+                .is_some_and(|count| range.contains(i64::from(count))),
+            FilterCondition::DesktopName(pattern) => match window.virtual_desktop {
+                VirtualDesktopInfo::AtDesktop { index, .. } => context
+                    .desktop_names
+                    .get(index as usize)
+                    .is_some_and(|name| pattern.is_match(name)),
+                _ => false,
+            },
+            FilterCondition::Schedule(schedule) => {
+                context.local_time.is_some_and(|now| schedule.contains(now))
+            }
+            FilterCondition::PinState(state) => PinState::of(window) == *state,
+        }
+    }
+    fn check_desktop_index(range: &IntegerRange, window: &WindowInfo) -> bool {
+        match window.virtual_desktop {
+            VirtualDesktopInfo::AtDesktop { index, .. } => range.contains(i64::from(index)),
+            _ => true,
+        }
+    }
+}
+
+/// Specifies what action to preform on a window that a [`WindowFilter`] has
+/// selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "persist_filters", derive(Serialize, Deserialize))]
+pub enum FilterAction {
+    /// Move the window to another virtual desktop. Do nothing if the window is
+    /// pinned and therefore visible on all desktops.
+    Move,
+    /// Move the window to another virtual desktop and if the window was pinned
+    /// then unpin it first.
+    UnpinAndMove,
+    /// Move the window to the virtual desktop that is active when the filter
+    /// is applied. Do nothing if the window is pinned.
+    BringHere,
+    /// Move the window to the virtual desktop that is active when the filter
+    /// is applied and if the window was pinned then unpin it first.
+    UnpinAndBringHere,
+    /// Spread matching windows across several virtual desktops, see
+    /// [`WindowFilter::distribute_to`]. Do nothing if the window is pinned.
+    Distribute,
+    /// Unpin the window so that it is no longer visible on all desktops.
+    Unpin,
+    /// Pin the window so that it becomes visible on all desktops.
+    Pin,
+    /// Unpin all windows of the window's process. Unlike the other actions
+    /// this also affects windows whose app is pinned.
+    UnpinApp,
+    /// Pin all windows of the window's process so that they become visible on
+    /// all desktops.
+    PinApp,
+    /// Minimize the window.
+    Minimize,
+    /// Restore the window from being minimized or maximized.
+    Restore,
+    /// Maximize the window.
+    Maximize,
+    /// Ask the window to close, the same as clicking its close button.
+    Close,
+    /// Do nothing with the window. This can be useful to prevent some windows
+    /// from being affected by any other filter.
+    Nothing,
+    /// Disable this filter. The program will act as if this filter didn't exist.
+    #[default]
+    Disabled,
+}
+impl FilterAction {
+    pub fn all() -> [FilterAction; 15] {
+        macro_rules! all {
+            ($($name:ident),* $(,)?) => {{
+                let _: fn(Self) = |this| {
+                    match this {
+                        $(Self::$name => (),)*
+                    }
+                };
+                [$(Self::$name,)*]
+            }};
+        }
+        all![
+            Move,
+            UnpinAndMove,
+            BringHere,
+            UnpinAndBringHere,
+            Distribute,
+            Unpin,
+            Pin,
+            UnpinApp,
+            PinApp,
+            Minimize,
+            Restore,
+            Maximize,
+            Close,
+            Nothing,
+            Disabled
+        ]
+    }
+    /// `true` if the action can change which virtual desktop a window is
+    /// shown on.
+    pub fn changes_placement(&self) -> bool {
+        match self {
+            FilterAction::Move
+            | FilterAction::UnpinAndMove
+            | FilterAction::BringHere
+            | FilterAction::UnpinAndBringHere
+            | FilterAction::Distribute
+            | FilterAction::Unpin
+            | FilterAction::Pin
+            | FilterAction::UnpinApp
+            | FilterAction::PinApp => true,
+            FilterAction::Minimize
+            | FilterAction::Restore
+            | FilterAction::Maximize
+            | FilterAction::Close
+            | FilterAction::Nothing
+            | FilterAction::Disabled => false,
+        }
+    }
+    /// `true` if the action moves windows to the current desktop.
+    pub fn brings_here(&self) -> bool {
+        matches!(
+            self,
+            FilterAction::BringHere | FilterAction::UnpinAndBringHere
+        )
+    }
+    /// `true` if the action affects all windows of a process rather than a
+    /// single window.
+    pub fn affects_app(&self) -> bool {
+        matches!(self, FilterAction::UnpinApp | FilterAction::PinApp)
+    }
+    /// `true` if the action minimizes, restores, maximizes or closes the
+    /// window.
+    pub fn manages_window(&self) -> bool {
+        matches!(
+            self,
+            FilterAction::Minimize
+                | FilterAction::Restore
+                | FilterAction::Maximize
+                | FilterAction::Close
+        )
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterAction::Move => "Move",
+            FilterAction::UnpinAndMove => "Unpin and move",
+            FilterAction::BringHere => "Bring here",
+            FilterAction::UnpinAndBringHere => "Unpin and bring here",
+            FilterAction::Distribute => "Distribute",
+            FilterAction::Unpin => "Unpin",
+            FilterAction::Pin => "Pin",
+            FilterAction::UnpinApp => "Unpin app",
+            FilterAction::PinApp => "Pin app",
+            FilterAction::Minimize => "Minimize",
+            FilterAction::Restore => "Restore",
+            FilterAction::Maximize => "Maximize",
+            FilterAction::Close => "Close",
+            FilterAction::Nothing => "Nothing",
+            FilterAction::Disabled => "Disabled",
+        }
+    }
+}
+/// Used to represent the action inside the configuration window.
+impl fmt::Display for FilterAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window() -> WindowInfo {
+        WindowInfo::for_test(
+            1,
+            "notepad",
+            "Notepad",
+            VirtualDesktopInfo::AtDesktop { index: 0 },
+        )
+    }
+
+    fn at(weekday: Weekday, time: &str) -> LocalTime {
+        LocalTime {
+            weekday,
+            time: time.parse().unwrap(),
+        }
+    }
+
+    fn schedule(days: &[Weekday], start: &str, end: &str) -> Schedule {
+        Schedule {
+            days: days.to_vec(),
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        }
+    }
+
+    /// A filter that only uses fields that version 1 can represent.
+    fn v1_filter() -> WindowFilter {
+        WindowFilter {
+            window_index: IntegerRange {
+                lower_bound: Some(1),
+                upper_bound: Some(2),
+            },
+            desktop_index: IntegerRange {
+                lower_bound: Some(0),
+                upper_bound: None,
+            },
+            window_title: TextPattern::from("*Notepad"),
+            process_name: TextPattern::from("notepad"),
+            action: FilterAction::UnpinAndMove,
+            target_desktop: 2,
+            ..WindowFilter::default()
+        }
+    }
+
+    /// Filters that each use one feature that was added in version 2.
+    fn v2_filters() -> Vec<(&'static str, WindowFilter)> {
+        let base = v1_filter();
+        let mut filters = vec![
+            (
+                "name",
+                WindowFilter {
+                    name: Arc::from("Editor"),
+                    ..base.clone()
+                },
+            ),
+            (
+                "tags",
+                WindowFilter {
+                    tags: vec![Arc::from("work")],
+                    ..base.clone()
+                },
+            ),
+            (
+                "group",
+                WindowFilter {
+                    group: Arc::from("Work"),
+                    ..base.clone()
+                },
+            ),
+            (
+                "window_index_set",
+                WindowFilter {
+                    window_index_set: Some(IntegerSet::new([1, 3], false)),
+                    ..base.clone()
+                },
+            ),
+            (
+                "desktop_index_set",
+                WindowFilter {
+                    desktop_index_set: Some(IntegerSet::new([0], true)),
+                    ..base.clone()
+                },
+            ),
+            (
+                "window_title mode",
+                WindowFilter {
+                    window_title: TextPattern::from("Notepad").with_mode(TextMatchMode::Exact),
+                    ..base.clone()
+                },
+            ),
+            (
+                "process_name options",
+                WindowFilter {
+                    process_name: TextPattern::from("notepad").with_options(TextMatchOptions {
+                        ignore_case: true,
+                        ..TextMatchOptions::default()
+                    }),
+                    ..base.clone()
+                },
+            ),
+            (
+                "window_class",
+                WindowFilter {
+                    window_class: TextPattern::from("Notepad"),
+                    ..base.clone()
+                },
+            ),
+            (
+                "executable_path",
+                WindowFilter {
+                    executable_path: TextPattern::from("C:\\Windows\\*"),
+                    ..base.clone()
+                },
+            ),
+            (
+                "conditions",
+                WindowFilter {
+                    conditions: Some(FilterCondition::Not(Box::new(FilterCondition::PinState(
+                        PinState::AppPinned,
+                    )))),
+                    ..base.clone()
+                },
+            ),
+            (
+                "dynamic_target",
+                WindowFilter {
+                    dynamic_target: Some(DesktopTarget::Current { offset: 1 }),
+                    ..base.clone()
+                },
+            ),
+            (
+                "create_missing_desktops",
+                WindowFilter {
+                    create_missing_desktops: Some(CreateDesktops::default()),
+                    ..base.clone()
+                },
+            ),
+            (
+                "distribute_to",
+                WindowFilter {
+                    distribute_to: Some(Distribution::default()),
+                    ..base.clone()
+                },
+            ),
+            (
+                "continue_after_match",
+                WindowFilter {
+                    continue_after_match: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "only_first_time",
+                WindowFilter {
+                    only_first_time: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "settle_delay_ms",
+                WindowFilter {
+                    settle_delay_ms: 500,
+                    ..base.clone()
+                },
+            ),
+            (
+                "apply_to_app_pinned",
+                WindowFilter {
+                    apply_to_app_pinned: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "focus_after_move",
+                WindowFilter {
+                    focus_after_move: true,
+                    ..base.clone()
+                },
+            ),
+            (
+                "stop_flashing",
+                WindowFilter {
+                    stop_flashing: Some(false),
+                    ..base.clone()
+                },
+            ),
+            (
+                "notify_on_move",
+                WindowFilter {
+                    notify_on_move: true,
+                    ..base.clone()
+                },
+            ),
+        ];
+        for action in FilterAction::all() {
+            if !matches!(
+                action,
+                FilterAction::Move
+                    | FilterAction::UnpinAndMove
+                    | FilterAction::Unpin
+                    | FilterAction::Pin
+                    | FilterAction::Nothing
+                    | FilterAction::Disabled
+            ) {
+                filters.push((
+                    action.as_str(),
+                    WindowFilter {
+                        action,
+                        ..base.clone()
+                    },
+                ));
+            }
+        }
+        filters
+    }
+
+    #[test]
+    fn v1_compatible_filters_export_as_version_1() {
+        let filters = [
+            FilterAction::Move,
+            FilterAction::UnpinAndMove,
+            FilterAction::Unpin,
+            FilterAction::Pin,
+            FilterAction::Nothing,
+            FilterAction::Disabled,
+        ]
+        .into_iter()
+        .map(|action| WindowFilter {
+            action,
+            ..v1_filter()
+        })
+        .chain([
+            // Conditions and index sets that can be stored as plain fields:
+            WindowFilter {
+                conditions: Some(FilterCondition::All(vec![FilterCondition::ProcessName(
+                    TextPattern::from("notepad"),
+                )])),
+                process_name: TextPattern::default(),
+                ..v1_filter()
+            },
+            WindowFilter {
+                window_index_set: Some(IntegerSet::new([1, 2, 3], false)),
+                ..v1_filter()
+            },
+        ])
+        .collect::<Vec<_>>();
+        let exported = ExportedWindowFilters::new(filters);
+        assert_eq!(exported.version, 1);
+        assert!(exported
+            .filters
+            .iter()
+            .all(|filter| filter.conditions.is_none() && filter.window_index_set.is_none()));
+    }
+
+    #[test]
+    fn new_fields_require_version_2() {
+        for (field, filter) in v2_filters() {
+            let exported = ExportedWindowFilters::new(vec![v1_filter(), filter]);
+            assert_eq!(exported.version, 2, "{field} should require version 2");
+        }
+        let exported = ExportedWindowFilters::new(vec![WindowFilter {
+            group: Arc::from("Work"),
+            ..v1_filter()
+        }]);
+        let groups = [FilterGroup {
+            name: Arc::from("Work"),
+            enabled: false,
+        }];
+        assert_eq!(exported.with_groups(&groups).version, 2);
+    }
+
+    #[test]
+    fn find_actions_stops_after_close() {
+        let filters = [
+            FilterAction::Minimize,
+            FilterAction::Close,
+            FilterAction::Move,
+        ]
+        .map(|action| WindowFilter {
+            action,
+            continue_after_match: true,
+            ..WindowFilter::default()
+        });
+        let actions = WindowFilter::find_actions(&filters, 0, &window(), &FilterContext::default())
+            .into_iter()
+            .map(|filter| filter.action)
+            .collect::<Vec<_>>();
+        assert_eq!(actions, [FilterAction::Minimize, FilterAction::Close]);
+    }
+
+    #[test]
+    fn only_first_time_is_remembered_per_filter() {
+        let filters = [0, 1].map(|_| WindowFilter {
+            only_first_time: true,
+            ..WindowFilter::default()
+        });
+        let mut seen = SeenWindows::default();
+        seen.observe_all(&[window()], Instant::now());
+        seen.mark_applied();
+        seen.mark_filter_applied(0, WindowHandle(1), 1);
+        let context = FilterContext::default().with_seen_windows(Arc::new(seen));
+        assert!(!filters[0].check_filter_at(0, 0, &window(), &context));
+        assert!(filters[1].check_filter_at(1, 0, &window(), &context));
+        // Another process that reused the window handle:
+        let other = WindowInfo {
+            process_id: 2,
+            ..window()
+        };
+        assert!(filters[0].check_filter_at(0, 0, &other, &context));
+    }
+
+    #[test]
+    fn remap_applied_filters() {
+        let filter = |title: &str| WindowFilter {
+            window_title: TextPattern::new(Arc::from(title)),
+            only_first_time: true,
+            ..WindowFilter::default()
+        };
+        let old = [filter("a"), filter("b"), filter("c")];
+        // "a" was removed, "b" was disabled and "c" moved to the front:
+        let new = [
+            filter("c"),
+            WindowFilter {
+                action: FilterAction::Disabled,
+                ..filter("b")
+            },
+        ];
+        let mut seen = SeenWindows::default();
+        seen.observe_all(&[window()], Instant::now());
+        for ix in 0..old.len() {
+            seen.mark_filter_applied(ix, WindowHandle(1), 1);
+        }
+        seen.remap_filters(&old, &new);
+        assert!(seen.was_applied(0, &window()));
+        assert!(seen.was_applied(1, &window()));
+        assert!(!seen.was_applied(2, &window()));
+
+        let mut seen = SeenWindows::default();
+        seen.observe_all(&[window()], Instant::now());
+        seen.mark_filter_applied(0, WindowHandle(1), 1);
+        seen.remap_filters(&old, &new);
+        assert!(!seen.was_applied(0, &window()));
+        assert!(!seen.was_applied(1, &window()));
+    }
+
+    #[test]
+    fn unsettled_filter_stops_evaluation() {
+        let filters = [
+            WindowFilter {
+                action: FilterAction::Move,
+                settle_delay_ms: 60_000,
+                ..WindowFilter::default()
+            },
+            WindowFilter {
+                action: FilterAction::Minimize,
+                ..WindowFilter::default()
+            },
+        ];
+        let mut seen = SeenWindows::default();
+        seen.observe_all(&[window()], Instant::now());
+        let context = FilterContext::default().with_seen_windows(Arc::new(seen));
+        let found =
+            WindowFilter::find_actions_among(filters.iter().enumerate(), 0, &window(), &context);
+        assert_eq!(found.actions, []);
+        assert!(found.recheck_after.is_some());
+        assert_eq!(
+            WindowFilter::find_first_action(&filters, 0, &window(), &context),
+            None
+        );
+
+        // Windows that haven't been observed don't wait:
+        let context = FilterContext::default();
+        let found =
+            WindowFilter::find_actions_among(filters.iter().enumerate(), 0, &window(), &context);
+        assert_eq!(found.actions, [(0, &filters[0])]);
+        assert_eq!(found.recheck_after, None);
+    }
+
+    #[test]
+    fn parse_time_of_day() {
+        assert_eq!("09:30".parse(), Ok(TimeOfDay::new(9, 30).unwrap()));
+        assert_eq!(" 7:05 ".parse(), Ok(TimeOfDay::new(7, 5).unwrap()));
+        assert_eq!("23:59".parse::<TimeOfDay>().unwrap().to_string(), "23:59");
+        for invalid in [
+            "", "9", "24:00", "12:60", "-1:00", "12:30:00", "noon", "12:",
+        ] {
+            assert!(
+                invalid.parse::<TimeOfDay>().is_err(),
+                "{invalid:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn schedule_within_a_day() {
+        let work_hours = schedule(
+            &[
+                Weekday::Monday,
+                Weekday::Tuesday,
+                Weekday::Wednesday,
+                Weekday::Thursday,
+                Weekday::Friday,
+            ],
+            "09:00",
+            "17:00",
+        );
+        assert!(work_hours.contains(at(Weekday::Monday, "09:00")));
+        assert!(work_hours.contains(at(Weekday::Friday, "16:59")));
+        assert!(!work_hours.contains(at(Weekday::Monday, "08:59")));
+        assert!(!work_hours.contains(at(Weekday::Monday, "17:00")));
+        assert!(!work_hours.contains(at(Weekday::Saturday, "12:00")));
+    }
+
+    #[test]
+    fn schedule_past_midnight() {
+        let friday_night = schedule(&[Weekday::Friday], "22:00", "06:00");
+        assert!(friday_night.contains(at(Weekday::Friday, "22:00")));
+        assert!(friday_night.contains(at(Weekday::Friday, "23:59")));
+        // The time after midnight belongs to the previous day:
+        assert!(friday_night.contains(at(Weekday::Saturday, "00:00")));
+        assert!(friday_night.contains(at(Weekday::Saturday, "05:59")));
+        assert!(!friday_night.contains(at(Weekday::Saturday, "06:00")));
+        assert!(!friday_night.contains(at(Weekday::Friday, "05:00")));
+        assert!(!friday_night.contains(at(Weekday::Saturday, "22:00")));
+        assert!(!friday_night.contains(at(Weekday::Friday, "21:59")));
+
+        let every_night = schedule(&[], "22:00", "06:00");
+        assert!(every_night.contains(at(Weekday::Monday, "03:00")));
+        assert!(!every_night.contains(at(Weekday::Monday, "12:00")));
+    }
+
+    #[test]
+    fn schedule_for_whole_days() {
+        let sunday = schedule(&[Weekday::Sunday], "00:00", "00:00");
+        assert!(sunday.contains(at(Weekday::Sunday, "00:00")));
+        assert!(sunday.contains(at(Weekday::Sunday, "23:59")));
+        assert!(!sunday.contains(at(Weekday::Monday, "00:00")));
+        assert!(schedule(&[], "00:00", "00:00").contains(at(Weekday::Wednesday, "12:00")));
+    }
+
+    #[test]
+    fn schedule_condition() {
+        let condition = FilterCondition::Schedule(schedule(&[Weekday::Monday], "09:00", "17:00"));
+        let check = |local_time| {
+            let context = FilterContext {
+                local_time,
+                ..FilterContext::default()
+            };
+            condition.check_window(0, &window(), &context)
+        };
+        assert!(check(Some(at(Weekday::Monday, "12:00"))));
+        assert!(!check(Some(at(Weekday::Monday, "18:00"))));
+        assert!(!check(Some(at(Weekday::Tuesday, "12:00"))));
+        // Schedules are never active if the time is unknown:
+        assert!(!check(None));
+    }
+
+    #[cfg(feature = "persist_filters_xml")]
+    #[test]
+    fn legacy_xml_export_reports_disabled_filters() {
+        let with_index_set = WindowFilter {
+            window_index_set: Some(IntegerSet::new([1, 3], false)),
+            ..v1_filter()
+        };
+        let exported = WindowFilter::serialize_to_xml(&[
+            v1_filter(),
+            with_index_set.clone(),
+            // Names can be dropped without changing what the filter does:
+            WindowFilter {
+                name: Arc::from("Notes"),
+                ..v1_filter()
+            },
+            with_index_set,
+        ])
+        .unwrap();
+        assert_eq!(exported.disabled_filters, [1, 3]);
+        assert_eq!(
+            exported.warning().unwrap(),
+            "Filters 2 and 4 use options that can't be represented in the legacy XML format, \
+            so they were exported as disabled."
+        );
+        let imported = WindowFilter::deserialize_from_xml(&exported.xml).unwrap();
+        assert_eq!(
+            imported.iter().map(|f| f.action).collect::<Vec<_>>(),
+            [
+                FilterAction::UnpinAndMove,
+                FilterAction::Disabled,
+                FilterAction::UnpinAndMove,
+                FilterAction::Disabled,
+            ]
+        );
+    }
+
+    #[cfg(feature = "persist_filters")]
+    #[test]
+    fn exported_filters_round_trip() {
+        let v1 = ExportedWindowFilters::new(vec![v1_filter()]);
+        let json = serde_json::to_value(&v1).unwrap();
+        // Version 1 files must only use fields that older versions know about:
+        let keys = json["filters"][0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in &keys {
+            assert!(
+                [
+                    "window_index",
+                    "desktop_index",
+                    "window_title",
+                    "process_name",
+                    "action",
+                    "target_desktop",
+                ]
+                .contains(&key.as_str()),
+                "unexpected field {key} in version 1 export"
+            );
+        }
+        assert_eq!(
+            serde_json::from_value::<ExportedWindowFilters>(json).unwrap(),
+            v1
+        );
+
+        for (field, filter) in v2_filters() {
+            let exported = ExportedWindowFilters::new(vec![filter]);
+            let json = serde_json::to_string(&exported).unwrap();
+            assert_eq!(
+                serde_json::from_str::<ExportedWindowFilters>(&json).unwrap(),
+                exported,
+                "{field} should survive a round trip"
+            );
+        }
+    }
+
+    #[cfg(feature = "persist_filters")]
+    #[test]
+    fn deserialize_time_of_day() {
+        assert_eq!(
+            serde_json::from_str::<TimeOfDay>("\"09:30\"").unwrap(),
+            TimeOfDay::new(9, 30).unwrap()
+        );
+        assert!(serde_json::from_str::<TimeOfDay>("\"25:00\"").is_err());
+    }
+}
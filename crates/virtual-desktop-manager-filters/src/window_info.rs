@@ -0,0 +1,86 @@
+//! Information about windows that filters are checked against.
+
+use std::{fmt, sync::Arc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualDesktopInfo {
+    WindowPinned,
+    AppPinned,
+    AtDesktop {
+        // Zero-based index for the virtual desktop when the info was gathered
+        // (it might have been moved after that).
+        index: u32,
+    },
+}
+impl fmt::Display for VirtualDesktopInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WindowPinned => write!(f, "Pinned Window"),
+            Self::AppPinned => write!(f, "Pinned App"),
+            Self::AtDesktop { index, .. } => fmt::Display::fmt(&(index + 1), f),
+        }
+    }
+}
+impl VirtualDesktopInfo {
+    /// Returns `true` if the virtual desktop info is [`WindowPinned`].
+    ///
+    /// [`WindowPinned`]: VirtualDesktopInfo::WindowPinned
+    #[must_use]
+    pub fn is_window_pinned(&self) -> bool {
+        matches!(self, Self::WindowPinned)
+    }
+
+    /// Returns `true` if the virtual desktop info is [`AppPinned`].
+    ///
+    /// [`AppPinned`]: VirtualDesktopInfo::AppPinned
+    #[must_use]
+    pub fn is_app_pinned(&self) -> bool {
+        matches!(self, Self::AppPinned)
+    }
+
+    /// Returns `true` if the virtual desktop info is [`AtDesktop`].
+    ///
+    /// [`AtDesktop`]: VirtualDesktopInfo::AtDesktop
+    #[must_use]
+    pub fn is_at_desktop(&self) -> bool {
+        matches!(self, Self::AtDesktop { .. })
+    }
+}
+
+/// The `HWND` of a window, stored as an integer so that it can be sent between
+/// threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WindowHandle(pub isize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowInfo {
+    pub handle: WindowHandle,
+    pub title: String,
+    pub class_name: String,
+    pub process_id: u32,
+    pub process_name: Arc<str>,
+    /// Full path to the executable of the process that owns the window.
+    pub executable_path: Arc<str>,
+    pub virtual_desktop: VirtualDesktopInfo,
+}
+impl WindowInfo {
+    /// Information about a made up window that is used by tests and
+    /// benchmarks. The process id is the same as the window handle.
+    #[doc(hidden)]
+    pub fn for_test(
+        handle: isize,
+        process_name: &str,
+        title: &str,
+        virtual_desktop: VirtualDesktopInfo,
+    ) -> Self {
+        Self {
+            handle: WindowHandle(handle),
+            title: title.to_owned(),
+            class_name: String::new(),
+            process_id: handle as u32,
+            process_name: Arc::from(process_name),
+            executable_path: Arc::from(format!("C:\\{process_name}.exe")),
+            virtual_desktop,
+        }
+    }
+}
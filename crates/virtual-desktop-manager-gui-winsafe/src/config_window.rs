@@ -14,10 +14,13 @@ use std::path::PathBuf;
 use std::rc::{Rc, Weak};
 use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::{mpsc, Arc};
+use virtual_desktop_manager_core::filter_lint::{self, FilterWarning};
 use virtual_desktop_manager_core::settings::{ConfigWindowInfo, UiSettings};
 use virtual_desktop_manager_core::vd;
-use virtual_desktop_manager_core::window_filter::{ExportedWindowFilters, WindowFilter};
-use virtual_desktop_manager_core::window_info::WindowInfo;
+use virtual_desktop_manager_core::window_filter::{
+    ExportedWindowFilters, FilterContext, FilterGroup, WindowFilter,
+};
+use virtual_desktop_manager_core::window_info::{self, WindowInfo};
 use winsafe::gui::Icon;
 use winsafe::msg::lvm::{EnableGroupView, SetItem};
 use winsafe::msg::wm::{CtlColorStatic, SetFont};
@@ -84,6 +87,9 @@ pub struct WinsafeSettingsWindow {
     shared: Arc<SharedState>,
     background_thread: RefCell<Option<BackgroundThread>>,
     has_queued_refresh: Cell<bool>,
+    /// Virtual desktop info used when checking filters against windows,
+    /// updated when window info is gathered.
+    filter_context: RefCell<FilterContext>,
     is_data_sorted: Cell<bool>,
     loaded_settings: RefCell<Arc<UiSettings>>,
 }
@@ -267,6 +273,7 @@ impl WinsafeSettingsWindow {
                 shared,
                 background_thread: RefCell::new(None),
                 has_queued_refresh: Cell::new(false),
+                filter_context: RefCell::new(FilterContext::default()),
                 is_data_sorted: Cell::new(true),
                 loaded_settings: RefCell::new(settings),
             }
@@ -386,7 +393,7 @@ impl WinsafeSettingsWindow {
                     .new
                     .clone();
                 this.reload_from_settings(&settings);
-                this.populate_filter_list(&settings.filters);
+                this.populate_filter_list(&settings.filters, &settings.filter_groups);
                 this.gather_window_info();
 
                 if let Some((x, y)) = settings.config_window.position {
@@ -743,14 +750,16 @@ impl WinsafeSettingsWindow {
 }
 /// Import/export filters
 impl WinsafeSettingsWindow {
-    pub fn export_filters_to_xml_string(&self) -> Result<String, Box<dyn Error>> {
+    /// Also returns a warning if some filters had to be exported as disabled.
+    pub fn export_filters_to_xml_string(&self) -> Result<(String, Option<String>), Box<dyn Error>> {
         #[cfg(feature = "persist_filters_xml")]
         {
             let filters = self.loaded_settings.borrow().filters.clone();
-            let data = WindowFilter::serialize_to_xml(&filters)
+            let exported = WindowFilter::serialize_to_xml(&filters)
                 .map_err(|e| format!("Failed to convert filters to legacy XML format:\n{e}"))?;
+            let warning = exported.warning();
 
-            Ok(data)
+            Ok((exported.xml, warning))
         }
         #[cfg(not(feature = "persist_filters_xml"))]
         {
@@ -765,10 +774,9 @@ impl WinsafeSettingsWindow {
     pub fn export_filters_to_json_string(&self) -> Result<String, Box<dyn Error>> {
         #[cfg(feature = "persist_filters")]
         {
-            let exported = ExportedWindowFilters {
-                filters: self.loaded_settings.borrow().filters.to_vec(),
-                ..Default::default()
-            };
+            let settings = self.loaded_settings.borrow();
+            let exported = ExportedWindowFilters::new(settings.filters.to_vec())
+                .with_groups(&settings.filter_groups);
             let data = serde_json::to_string_pretty(&exported)
                 .map_err(|e| format!("Failed to convert filters to JSON:\n{e}"))?;
             Ok(data)
@@ -783,10 +791,12 @@ impl WinsafeSettingsWindow {
             )
         }
     }
+    /// Returns a warning that should be shown to the user if some filters
+    /// couldn't be exported faithfully.
     pub fn export_filters_to_file_path(
         &self,
         mut file_path: PathBuf,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<Option<String>, Box<dyn Error>> {
         // The file dialog should have asked about overwriting existing file.
         let mut allow_overwrite = true;
 
@@ -798,10 +808,10 @@ impl WinsafeSettingsWindow {
             false
         };
 
-        let data = if is_legacy {
+        let (data, warning) = if is_legacy {
             self.export_filters_to_xml_string()?
         } else {
-            self.export_filters_to_json_string()?
+            (self.export_filters_to_json_string()?, None)
         };
 
         let mut file = OpenOptions::new()
@@ -819,7 +829,7 @@ impl WinsafeSettingsWindow {
             )
         })?;
 
-        Ok(())
+        Ok(warning)
     }
     pub fn export_filters_with_dialog(&self) {
         let Some(selected_file_path) = rfd::FileDialog::new()
@@ -835,14 +845,26 @@ impl WinsafeSettingsWindow {
             return;
         };
 
-        if let Err(e) = self.export_filters_to_file_path(selected_file_path) {
-            rfd::MessageDialog::new()
-                .set_title("Virtual Desktop Manager - Export error")
-                .set_description(&e.to_string())
-                .set_buttons(rfd::MessageButtons::Ok)
-                .set_level(rfd::MessageLevel::Error)
-                .set_parent(&WinsafeHandleToRawHandle(self.wnd.hwnd()))
-                .show();
+        match self.export_filters_to_file_path(selected_file_path) {
+            Ok(None) => {}
+            Ok(Some(warning)) => {
+                rfd::MessageDialog::new()
+                    .set_title("Virtual Desktop Manager - Export warning")
+                    .set_description(&warning)
+                    .set_buttons(rfd::MessageButtons::Ok)
+                    .set_level(rfd::MessageLevel::Warning)
+                    .set_parent(&WinsafeHandleToRawHandle(self.wnd.hwnd()))
+                    .show();
+            }
+            Err(e) => {
+                rfd::MessageDialog::new()
+                    .set_title("Virtual Desktop Manager - Export error")
+                    .set_description(&e.to_string())
+                    .set_buttons(rfd::MessageButtons::Ok)
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_parent(&WinsafeHandleToRawHandle(self.wnd.hwnd()))
+                    .show();
+            }
         }
     }
     pub fn import_filters_from_legacy_xml(&self, xml: String) -> Result<(), Box<dyn Error>> {
@@ -882,12 +904,13 @@ impl WinsafeSettingsWindow {
                     serde_path_to_error::deserialize(&mut deserializer)
                 }
             };
-            let imported = result
+            let (imported, imported_groups) = result
                 .map_err(|e| format!("Failed to parse JSON filters/rules:\n{e}"))?
-                .migrate_and_get_filters();
+                .migrate_and_get_filters_with_groups();
 
             self.update_settings(|prev| UiSettings {
                 filters: prev.filters.iter().cloned().chain(imported).collect(),
+                filter_groups: FilterGroup::merge(&prev.filter_groups, imported_groups),
                 ..prev.clone()
             });
 
@@ -1041,13 +1064,14 @@ impl WinsafeSettingsWindow {
         window_index: i32,
         window: &WindowInfo,
     ) -> String {
+        let filter_context = self.filter_context.borrow();
         self.loaded_settings
             .borrow()
             .filters
             .iter()
             .enumerate()
             // Find filters/rules that apply to this window:
-            .filter(|(_, rule)| rule.check_window(window_index, window))
+            .filter(|(_, rule)| rule.check_window(window_index, window, &filter_context))
             // one-based indexes:
             .map(|(ix, _)| (ix + 1).to_string())
             .collect::<Vec<_>>()
@@ -1060,20 +1084,29 @@ impl WinsafeSettingsWindow {
             .iter()
             .filter(|item| item.data().borrow().1.is_window_info())
             .count();
+        // Windows are loaded in order, so earlier windows of the same process
+        // are already known:
+        self.filter_context
+            .borrow_mut()
+            .process_windows
+            .add(&window);
 
         let filter_indexes = self.determine_active_filter_indexes_for_window(index as i32, &window);
-        let action = WindowFilter::find_first_action(
+        let action = WindowFilter::display_actions(&WindowFilter::find_actions(
             &self.loaded_settings.borrow().filters,
             index as i32,
             &window,
-        )
-        .map(|filter| filter.display_target_desktop().to_string());
+            &self.filter_context.borrow(),
+        ))
+        .to_string();
 
         let WindowInfo {
             handle: _,
             title,
+            class_name: _,
             process_id: _,
             process_name,
+            executable_path: _,
             virtual_desktop,
         } = window.clone();
 
@@ -1085,7 +1118,7 @@ impl WinsafeSettingsWindow {
             title.as_str(),
             &*process_name,
             filter_indexes.as_str(),
-            action.as_deref().unwrap_or_default(),
+            action.as_str(),
         ];
         match self
             .data_view
@@ -1126,6 +1159,15 @@ impl WinsafeSettingsWindow {
         }
         self.clear_window_info();
         self.has_queued_refresh.set(false);
+        let filter_context = window_info::gather_filter_context();
+        let desktop_count_changed =
+            self.filter_context.borrow().desktop_count != filter_context.desktop_count;
+        *self.filter_context.borrow_mut() = filter_context;
+        if desktop_count_changed {
+            // Warnings about target desktops depend on the number of desktops:
+            let settings = self.loaded_settings.borrow().clone();
+            self.populate_filter_list(&settings.filters, &settings.filter_groups);
+        }
 
         let (tx, rx) = mpsc::channel();
         let wnd = self.wnd.clone();
@@ -1145,7 +1187,7 @@ impl WinsafeSettingsWindow {
                                 );
                         }
                     }
-                    for result in WindowInfo::try_get_all() {
+                    for result in window_info::try_get_all_window_info() {
                         if let Ok(window) = result {
                             tracing::trace!(info = ?window, "Sending window info to config window");
                             if tx.send(window).is_err() {
@@ -1206,12 +1248,14 @@ impl WinsafeSettingsWindow {
                 tracing::error!(error =? e, "Failed to update shown filter indexes");
             }
 
-            let action = WindowFilter::find_first_action(&filters, *index as i32, &window_info)
-                .map(|filter| filter.display_target_desktop().to_string());
-            if let Err(e) = item.set_text(
-                Self::COLUMN_TARGET_DESKTOP,
-                action.unwrap_or_default().as_str(),
-            ) {
+            let action = WindowFilter::display_actions(&WindowFilter::find_actions(
+                &filters,
+                *index as i32,
+                &window_info,
+                &self.filter_context.borrow(),
+            ))
+            .to_string();
+            if let Err(e) = item.set_text(Self::COLUMN_TARGET_DESKTOP, action.as_str()) {
                 tracing::error!(error =? e, "Failed to update shown filter indexes");
             }
         }
@@ -1238,12 +1282,12 @@ impl WinsafeSettingsWindow {
     }
     fn reload_from_settings(&self, new: &Arc<UiSettings>) {
         let prev = self.loaded_settings.replace(new.clone());
-        if !Arc::ptr_eq(&prev.filters, &new.filters) {
-            self.populate_filter_list(&new.filters)
+        if !Arc::ptr_eq(&prev.filters, &new.filters) || prev.filter_groups != new.filter_groups {
+            self.populate_filter_list(&new.filters, &new.filter_groups)
         }
         self.program_settings_panel.set_settings_data(new);
     }
-    fn populate_filter_list(&self, filters: &Arc<[WindowFilter]>) {
+    fn populate_filter_list(&self, filters: &Arc<[WindowFilter]>, groups: &[FilterGroup]) {
         // Update existing filter items:
         let existing_filter_rows: Vec<(_, WindowFilter)> = self
             .data_view
@@ -1264,26 +1308,73 @@ impl WinsafeSettingsWindow {
             "WinsafeSettingsWindow::populate_filter_list"
         );
 
-        fn get_filter_columns(filter_index: usize, filter: &WindowFilter) -> [String; 6] {
+        fn get_filter_action_column(
+            filter_index: usize,
+            filter: &WindowFilter,
+            warnings: &[FilterWarning],
+        ) -> String {
+            let mut text = filter.display_target_desktop().to_string();
+            let mut filter_warnings = warnings
+                .iter()
+                .filter(|warning| warning.filter_index == filter_index);
+            if let Some(first) = filter_warnings.next() {
+                text.push_str(&format!(" (Warning: {}", first.kind));
+                let more = filter_warnings.count();
+                if more > 0 {
+                    text.push_str(&format!(", +{more} more"));
+                }
+                text.push(')');
+            }
+            text
+        }
+        fn get_filter_columns(
+            filter_index: usize,
+            filter: &WindowFilter,
+            warnings: &[FilterWarning],
+        ) -> [String; 6] {
             let WindowFilter {
-                window_index,
-                desktop_index,
+                name: _,
+                tags: _,
+                group: _,
+                window_index: _,
+                desktop_index: _,
+                window_index_set: _,
+                desktop_index_set: _,
                 window_title,
                 process_name,
+                window_class: _,
+                executable_path: _,
+                conditions: _,
                 action: _,
                 target_desktop: _,
+                dynamic_target: _,
+                create_missing_desktops: _,
+                distribute_to: _,
+                continue_after_match: _,
+                only_first_time: _,
+                settle_delay_ms: _,
+                apply_to_app_pinned: _,
+                focus_after_move: _,
+                stop_flashing: _,
+                notify_on_move: _,
             } = filter;
 
             [
-                window_index.into_one_based_indexes().to_string(),
-                desktop_index.into_one_based_indexes().to_string(),
-                window_title.display_escaped_newline_glob().to_string(),
-                process_name.display_escaped_newline_glob().to_string(),
+                filter.display_window_index().to_string(),
+                filter.display_desktop_index().to_string(),
+                window_title.display_escaped().to_string(),
+                process_name.display_escaped().to_string(),
                 filter_index.saturating_add(1).to_string(),
-                filter.display_target_desktop().to_string(),
+                get_filter_action_column(filter_index, filter, warnings),
             ]
         }
 
+        let warnings =
+            filter_lint::lint_filters(filters, groups, self.filter_context.borrow().desktop_count);
+        for warning in &warnings {
+            tracing::debug!(%warning, "Found problem with filter");
+        }
+
         let mut did_change = false;
         let mut did_delete = false;
 
@@ -1299,13 +1390,19 @@ impl WinsafeSettingsWindow {
                     );
                     if prev != new {
                         did_change = true;
-                        let info = get_filter_columns(index, new);
+                        let info = get_filter_columns(index, new, &warnings);
                         for (column_ix, text) in info.into_iter().enumerate() {
                             if let Err(e) = existing_row.set_text(column_ix as u32, text.as_str()) {
                                 tracing::error!(error = ?e, column_index = column_ix, "failed to update column text for filter");
                             }
                         }
                         existing_row.data().borrow_mut().1 = DataViewItem::Filter(new.clone());
+                    } else {
+                        // Warnings can change even if the filter didn't:
+                        let text = get_filter_action_column(index, new, &warnings);
+                        if let Err(e) = existing_row.set_text(Self::COLUMN_TARGET_DESKTOP, &text) {
+                            tracing::error!(error = ?e, "Failed to update filter warnings");
+                        }
                     }
                 }
                 (Some((_, _)), None) => {
@@ -1321,7 +1418,7 @@ impl WinsafeSettingsWindow {
                 (None, Some(new)) => {
                     // No existing row so create one
                     did_change = true;
-                    let info = get_filter_columns(index, new);
+                    let info = get_filter_columns(index, new, &warnings);
                     match self.data_view.items().add(
                         &info,
                         None,
@@ -1431,13 +1528,11 @@ impl FilterOptionsHooks for Weak<WinsafeSettingsWindow> {
         let Some(index) = this.filter_options_panel.get_selected_filter_index() else {
             return;
         };
-        let filter_data = this.filter_options_panel.get_filter_data();
-        tracing::trace!(filter_data = ?filter_data, filter_index = index, "New filter options");
-
         this.update_settings(|prev| {
             let mut filters = prev.filters.clone();
             if let Some(filter) = Arc::make_mut(&mut filters).get_mut(index) {
-                *filter = filter_data;
+                *filter = this.filter_options_panel.get_filter_data(filter);
+                tracing::trace!(filter_data = ?filter, filter_index = index, "New filter options");
             }
             UiSettings {
                 filters,
@@ -1542,7 +1637,11 @@ impl ProgramSettingsHooks for Weak<WinsafeSettingsWindow> {
             .get_settings_data(&mut quick_switch_menu_shortcuts_error);
         this.update_settings(|prev| UiSettings {
             filters: prev.filters.clone(),
+            filter_groups: prev.filter_groups.clone(),
             config_window: prev.config_window.clone(),
+            window_exclusions: this
+                .program_settings_panel
+                .get_window_exclusions(&prev.window_exclusions),
             quick_switch_menu_shortcuts: if quick_switch_menu_shortcuts_error {
                 tracing::warn!("UI data for \"quick_switch_menu_shortcuts\" had errors so resetting to last known good state.");
                 prev.quick_switch_menu_shortcuts.clone()
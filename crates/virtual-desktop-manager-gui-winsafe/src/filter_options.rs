@@ -2,7 +2,7 @@ use crate::layout::LayoutArea;
 use crate::{custom_msg, GuiParentWithEvents};
 use std::rc::Rc;
 use virtual_desktop_manager_core::window_filter::{
-    FilterAction, IntegerRange, TextPattern, WindowFilter,
+    FilterAction, IntegerRange, TextMatchMode, TextMatchOptions, TextPattern, WindowFilter,
 };
 use winsafe::co;
 use winsafe::gui;
@@ -67,11 +67,19 @@ pub struct FilterOptionsPanel {
     virtual_desktop_index_label: gui::Label,
     virtual_desktop_index_range: Rc<RangeControl>,
     window_title_label: gui::Label,
+    window_title_mode: gui::ComboBox,
     window_title_input: gui::Edit,
+    window_title_ignore_case: gui::CheckBox,
     process_name_label: gui::Label,
+    process_name_mode: gui::ComboBox,
     process_name_input: gui::Edit,
+    process_name_ignore_case: gui::CheckBox,
     action_label: gui::Label,
     action: gui::ComboBox,
+    continue_after_match: gui::CheckBox,
+    only_first_time: gui::CheckBox,
+    apply_to_app_pinned: gui::CheckBox,
+    focus_after_move: gui::CheckBox,
     target_desktop_label: gui::Label,
     target_desktop_input: gui::Edit,
     target_desktop_up_down: gui::UpDown,
@@ -93,6 +101,7 @@ impl FilterOptionsPanel {
         let label_height = 20;
         let input_height = 25;
         let button_height = 30;
+        let checkbox_height = 20;
 
         let selected_filter_index_label = gui::Label::new(
             parent,
@@ -228,16 +237,30 @@ impl FilterOptionsPanel {
         // Extra space from previous controls (new grouping)
         layout.take_top_with_margin(layout.margin, 0);
 
+        let [window_title_label_layout, window_title_mode_layout] =
+            layout.take_top(button_height).split_horizontal();
         let window_title_label = gui::Label::new(
             parent,
             gui::LabelOpts {
                 text: "Window title:",
-                position: layout.dpi_pos(),
-                size: (layout.dpi_width(), gui::dpi_y(label_height)),
+                position: window_title_label_layout.dpi_pos(),
+                size: (
+                    window_title_label_layout.dpi_width(),
+                    gui::dpi_y(label_height),
+                ),
+                ..Default::default()
+            },
+        );
+        let window_title_mode = gui::ComboBox::new(
+            parent,
+            gui::ComboBoxOpts {
+                position: window_title_mode_layout.dpi_pos(),
+                width: window_title_mode_layout.dpi_width(),
+                items: &TextMatchMode::all().map(|mode| mode.as_str()),
+                selected_item: Some(0),
                 ..Default::default()
             },
         );
-        layout.take_top(label_height);
 
         let window_title_layout = layout.take_top(input_height * 3 + input_height / 2);
         let window_title_input = gui::Edit::new(
@@ -258,19 +281,44 @@ impl FilterOptionsPanel {
             },
         );
 
+        let window_title_ignore_case_layout = layout.take_top(checkbox_height);
+        let window_title_ignore_case = gui::CheckBox::new(
+            parent,
+            gui::CheckBoxOpts {
+                text: "Ignore case",
+                position: window_title_ignore_case_layout.dpi_pos(),
+                size: window_title_ignore_case_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+
         // Extra space from previous controls (new grouping)
         layout.take_top_with_margin(layout.margin, 0);
 
+        let [process_name_label_layout, process_name_mode_layout] =
+            layout.take_top(button_height).split_horizontal();
         let process_name_label = gui::Label::new(
             parent,
             gui::LabelOpts {
                 text: "Process name:",
-                position: layout.dpi_pos(),
-                size: (layout.dpi_width(), gui::dpi_y(label_height)),
+                position: process_name_label_layout.dpi_pos(),
+                size: (
+                    process_name_label_layout.dpi_width(),
+                    gui::dpi_y(label_height),
+                ),
+                ..Default::default()
+            },
+        );
+        let process_name_mode = gui::ComboBox::new(
+            parent,
+            gui::ComboBoxOpts {
+                position: process_name_mode_layout.dpi_pos(),
+                width: process_name_mode_layout.dpi_width(),
+                items: &TextMatchMode::all().map(|mode| mode.as_str()),
+                selected_item: Some(0),
                 ..Default::default()
             },
         );
-        layout.take_top(label_height);
 
         let process_name_layout = layout.take_top(input_height * 3 + input_height / 2);
         let process_name_input = gui::Edit::new(
@@ -291,6 +339,17 @@ impl FilterOptionsPanel {
             },
         );
 
+        let process_name_ignore_case_layout = layout.take_top(checkbox_height);
+        let process_name_ignore_case = gui::CheckBox::new(
+            parent,
+            gui::CheckBoxOpts {
+                text: "Ignore case",
+                position: process_name_ignore_case_layout.dpi_pos(),
+                size: process_name_ignore_case_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+
         // Extra space from previous controls (new grouping)
         layout.take_top_with_margin(layout.margin, 0);
 
@@ -317,6 +376,50 @@ impl FilterOptionsPanel {
             },
         );
 
+        let continue_after_match_layout = layout.take_top(checkbox_height);
+        let continue_after_match = gui::CheckBox::new(
+            parent,
+            gui::CheckBoxOpts {
+                text: "Continue with later filters",
+                position: continue_after_match_layout.dpi_pos(),
+                size: continue_after_match_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+
+        let only_first_time_layout = layout.take_top(checkbox_height);
+        let only_first_time = gui::CheckBox::new(
+            parent,
+            gui::CheckBoxOpts {
+                text: "Only first time a window is seen",
+                position: only_first_time_layout.dpi_pos(),
+                size: only_first_time_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+
+        let apply_to_app_pinned_layout = layout.take_top(checkbox_height);
+        let apply_to_app_pinned = gui::CheckBox::new(
+            parent,
+            gui::CheckBoxOpts {
+                text: "Also affect pinned apps",
+                position: apply_to_app_pinned_layout.dpi_pos(),
+                size: apply_to_app_pinned_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+
+        let focus_after_move_layout = layout.take_top(checkbox_height);
+        let focus_after_move = gui::CheckBox::new(
+            parent,
+            gui::CheckBoxOpts {
+                text: "Focus windows after moving them",
+                position: focus_after_move_layout.dpi_pos(),
+                size: focus_after_move_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+
         // Extra space from previous controls (new grouping)
         layout.take_top_with_margin(layout.margin, 0);
 
@@ -366,11 +469,19 @@ impl FilterOptionsPanel {
             virtual_desktop_index_label,
             virtual_desktop_index_range,
             window_title_label,
+            window_title_mode,
             window_title_input,
+            window_title_ignore_case,
             process_name_label,
+            process_name_mode,
             process_name_input,
+            process_name_ignore_case,
             action_label,
             action,
+            continue_after_match,
+            only_first_time,
+            apply_to_app_pinned,
+            focus_after_move,
             target_desktop_label,
             target_desktop_input,
             target_desktop_up_down,
@@ -392,11 +503,19 @@ impl FilterOptionsPanel {
             self.window_index_label.hwnd(),
             self.virtual_desktop_index_label.hwnd(),
             self.window_title_label.hwnd(),
+            self.window_title_mode.hwnd(),
             self.window_title_input.hwnd(),
+            self.window_title_ignore_case.hwnd(),
             self.process_name_label.hwnd(),
+            self.process_name_mode.hwnd(),
             self.process_name_input.hwnd(),
+            self.process_name_ignore_case.hwnd(),
             self.action_label.hwnd(),
             self.action.hwnd(),
+            self.continue_after_match.hwnd(),
+            self.only_first_time.hwnd(),
+            self.apply_to_app_pinned.hwnd(),
+            self.focus_after_move.hwnd(),
             self.target_desktop_label.hwnd(),
             self.target_desktop_input.hwnd(),
             self.target_desktop_up_down.hwnd(),
@@ -492,6 +611,23 @@ impl FilterOptionsPanel {
                 }
             });
         }
+        let option_checkbox_handlers: [(&gui::CheckBox, FilterChange); _] = [
+            (&self.window_title_ignore_case, FilterChange::WindowTitle),
+            (&self.process_name_ignore_case, FilterChange::ProcessName),
+            (&self.continue_after_match, FilterChange::Action),
+            (&self.only_first_time, FilterChange::Action),
+            (&self.apply_to_app_pinned, FilterChange::Action),
+            (&self.focus_after_move, FilterChange::Action),
+        ];
+        for (checkbox, change) in option_checkbox_handlers {
+            checkbox.on().bn_clicked({
+                let parent = parent.clone();
+                move || {
+                    Self::post_change(&parent, change.clone());
+                    Ok(())
+                }
+            });
+        }
 
         let input_handlers: [(&gui::Edit, fn(&H, &P)); _] = [
             (&self.selected_filter_index_input, |_, parent| {
@@ -532,13 +668,20 @@ impl FilterOptionsPanel {
             });
         }
 
-        self.action.on().cbn_sel_change({
-            let parent = parent.clone();
-            move || {
-                Self::post_change(&parent, FilterChange::Action);
-                Ok(())
-            }
-        });
+        let combobox_handlers: [(&gui::ComboBox, FilterChange); _] = [
+            (&self.window_title_mode, FilterChange::WindowTitle),
+            (&self.process_name_mode, FilterChange::ProcessName),
+            (&self.action, FilterChange::Action),
+        ];
+        for (combobox, change) in combobox_handlers {
+            combobox.on().cbn_sel_change({
+                let parent = parent.clone();
+                move || {
+                    Self::post_change(&parent, change.clone());
+                    Ok(())
+                }
+            });
+        }
 
         // Delayed change events (so that reading values from controls will get the latest values):
         parent.on().wm(custom_msg::WM_FILTER_CHANGED, {
@@ -609,9 +752,17 @@ impl FilterOptionsPanel {
         self.btn_move_down.hwnd().EnableWindow(enabled);
         self.window_index_range.set_enabled(enabled);
         self.virtual_desktop_index_range.set_enabled(enabled);
+        self.window_title_mode.hwnd().EnableWindow(enabled);
         self.window_title_input.hwnd().EnableWindow(enabled);
+        self.window_title_ignore_case.hwnd().EnableWindow(enabled);
+        self.process_name_mode.hwnd().EnableWindow(enabled);
         self.process_name_input.hwnd().EnableWindow(enabled);
+        self.process_name_ignore_case.hwnd().EnableWindow(enabled);
         self.action.hwnd().EnableWindow(enabled);
+        self.continue_after_match.hwnd().EnableWindow(enabled);
+        self.only_first_time.hwnd().EnableWindow(enabled);
+        self.apply_to_app_pinned.hwnd().EnableWindow(enabled);
+        self.focus_after_move.hwnd().EnableWindow(enabled);
         self.target_desktop_input.hwnd().EnableWindow(enabled);
         self.set_enabled_ranges();
     }
@@ -634,19 +785,59 @@ impl FilterOptionsPanel {
             .get_range()
             .from_one_based_indexes()
     }
-    pub fn get_window_title(&self) -> TextPattern {
+    fn get_text_match_mode(combobox: &gui::ComboBox) -> TextMatchMode {
+        combobox
+            .items()
+            .selected_index()
+            .map(|index| TextMatchMode::all()[index as usize])
+            .unwrap_or_default()
+    }
+    fn set_text_match_mode(combobox: &gui::ComboBox, mode: TextMatchMode) {
+        if Self::get_text_match_mode(combobox) == mode {
+            return;
+        }
+        combobox.items().select(
+            TextMatchMode::all()
+                .iter()
+                .position(|&m| m == mode)
+                .map(|pos| pos as u32),
+        )
+    }
+    /// Text match options that aren't shown in the panel are copied from
+    /// `prev`.
+    fn get_text_match_options(ignore_case: &gui::CheckBox, prev: &TextPattern) -> TextMatchOptions {
+        TextMatchOptions {
+            ignore_case: ignore_case.is_checked(),
+            ..prev.options()
+        }
+    }
+    pub fn get_window_title(&self, prev: &TextPattern) -> TextPattern {
         self.window_title_input
             .text()
-            .map(|v| TextPattern::from(v.as_str()))
+            .map(|v| {
+                TextPattern::from(v.as_str())
+                    .with_mode(Self::get_text_match_mode(&self.window_title_mode))
+                    .with_options(Self::get_text_match_options(
+                        &self.window_title_ignore_case,
+                        prev,
+                    ))
+            })
             .unwrap_or_else(|e| {
                 tracing::error!(error = ?e, "Failed to read text from window title input field");
                 Default::default()
             })
     }
-    pub fn get_process_name(&self) -> TextPattern {
+    pub fn get_process_name(&self, prev: &TextPattern) -> TextPattern {
         self.process_name_input
             .text()
-            .map(|v| TextPattern::from(v.as_str()))
+            .map(|v| {
+                TextPattern::from(v.as_str())
+                    .with_mode(Self::get_text_match_mode(&self.process_name_mode))
+                    .with_options(Self::get_text_match_options(
+                        &self.process_name_ignore_case,
+                        prev,
+                    ))
+            })
             .unwrap_or_else(|e| {
                 tracing::error!(error = ?e, "Failed to read text from process name input field");
                 Default::default()
@@ -659,17 +850,36 @@ impl FilterOptionsPanel {
             .map(|index| FilterAction::all()[index as usize])
             .unwrap_or_default()
     }
+    pub fn get_continue_after_match(&self) -> bool {
+        self.continue_after_match.is_checked()
+    }
+    pub fn get_only_first_time(&self) -> bool {
+        self.only_first_time.is_checked()
+    }
+    pub fn get_apply_to_app_pinned(&self) -> bool {
+        self.apply_to_app_pinned.is_checked()
+    }
+    pub fn get_focus_after_move(&self) -> bool {
+        self.focus_after_move.is_checked()
+    }
     pub fn get_target_desktop(&self) -> i64 {
         i64::from(self.target_desktop_up_down.pos().saturating_sub(1))
     }
-    pub fn get_filter_data(&self) -> WindowFilter {
+    /// Read the filter from the panel's controls. Options that can't be edited
+    /// in the panel are copied from `prev`.
+    pub fn get_filter_data(&self, prev: &WindowFilter) -> WindowFilter {
         WindowFilter {
             window_index: self.get_window_index_range(),
             desktop_index: self.get_desktop_index_range(),
-            window_title: self.get_window_title(),
-            process_name: self.get_process_name(),
+            window_title: self.get_window_title(&prev.window_title),
+            process_name: self.get_process_name(&prev.process_name),
             action: self.get_filter_action(),
             target_desktop: self.get_target_desktop(),
+            continue_after_match: self.get_continue_after_match(),
+            only_first_time: self.get_only_first_time(),
+            apply_to_app_pinned: self.get_apply_to_app_pinned(),
+            focus_after_move: self.get_focus_after_move(),
+            ..prev.clone()
         }
     }
 }
@@ -703,7 +913,13 @@ impl FilterOptionsPanel {
         self.set_enabled_ranges();
     }
     pub fn set_window_title(&self, text: &TextPattern) {
-        if self.get_window_title() == *text {
+        if self.get_window_title(text) == *text {
+            return;
+        }
+        Self::set_text_match_mode(&self.window_title_mode, text.mode());
+        self.window_title_ignore_case
+            .set_check(text.options().ignore_case);
+        if self.get_window_title(text).pattern() == text.pattern() {
             return;
         }
         if let Err(e) = self.window_title_input.set_text(text.pattern()) {
@@ -711,7 +927,13 @@ impl FilterOptionsPanel {
         }
     }
     pub fn set_process_name(&self, text: &TextPattern) {
-        if self.get_process_name() == *text {
+        if self.get_process_name(text) == *text {
+            return;
+        }
+        Self::set_text_match_mode(&self.process_name_mode, text.mode());
+        self.process_name_ignore_case
+            .set_check(text.options().ignore_case);
+        if self.get_process_name(text).pattern() == text.pattern() {
             return;
         }
         if let Err(e) = self.process_name_input.set_text(text.pattern()) {
@@ -729,6 +951,30 @@ impl FilterOptionsPanel {
                 .map(|pos| pos as u32),
         )
     }
+    pub fn set_continue_after_match(&self, value: bool) {
+        if self.get_continue_after_match() == value {
+            return;
+        }
+        self.continue_after_match.set_check(value);
+    }
+    pub fn set_only_first_time(&self, value: bool) {
+        if self.get_only_first_time() == value {
+            return;
+        }
+        self.only_first_time.set_check(value);
+    }
+    pub fn set_apply_to_app_pinned(&self, value: bool) {
+        if self.get_apply_to_app_pinned() == value {
+            return;
+        }
+        self.apply_to_app_pinned.set_check(value);
+    }
+    pub fn set_focus_after_move(&self, value: bool) {
+        if self.get_focus_after_move() == value {
+            return;
+        }
+        self.focus_after_move.set_check(value);
+    }
     pub fn set_target_desktop(&self, desktop_index: i64) {
         if self.get_target_desktop() == desktop_index {
             return;
@@ -742,8 +988,27 @@ impl FilterOptionsPanel {
             desktop_index,
             window_title,
             process_name,
+            // Can't be edited in the panel:
+            window_index_set: _,
+            desktop_index_set: _,
+            name: _,
+            tags: _,
+            group: _,
+            window_class: _,
+            executable_path: _,
+            conditions: _,
             action,
             target_desktop,
+            dynamic_target: _,
+            create_missing_desktops: _,
+            distribute_to: _,
+            continue_after_match,
+            only_first_time,
+            settle_delay_ms: _,
+            apply_to_app_pinned,
+            focus_after_move,
+            stop_flashing: _,
+            notify_on_move: _,
         } = filter;
         self.set_window_index_range(*window_index);
         self.set_desktop_index_range(*desktop_index);
@@ -751,6 +1016,10 @@ impl FilterOptionsPanel {
         self.set_process_name(process_name);
         self.set_filter_action(*action);
         self.set_target_desktop(*target_desktop);
+        self.set_continue_after_match(*continue_after_match);
+        self.set_only_first_time(*only_first_time);
+        self.set_apply_to_app_pinned(*apply_to_app_pinned);
+        self.set_focus_after_move(*focus_after_move);
     }
 }
 
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use virtual_desktop_manager_core::settings::{
     AutoStart, QuickSwitchMenu, TrayClickAction, TrayIconType, UiSettings,
 };
+use virtual_desktop_manager_core::{filter_lint, window_filter::WindowExclusions};
 use winsafe::co;
 use winsafe::gui;
 use winsafe::msg::wm::SetFont;
@@ -43,6 +44,10 @@ pub struct ProgramSettingsPanel {
     menu_at_mouse_pos_hotkey_label_layout: gui::Label,
     menu_at_mouse_pos_hotkey_input: gui::Edit,
     menu_at_mouse_pos_hotkey_status: gui::Edit,
+    excluded_processes_label: gui::Label,
+    excluded_processes_input: gui::Edit,
+    excluded_titles_label: gui::Label,
+    excluded_titles_input: gui::Edit,
     tooltips: OnceCell<nwg::Tooltip>,
     is_manually_setting: Cell<u32>,
 }
@@ -352,6 +357,52 @@ impl ProgramSettingsPanel {
             },
         );
 
+        layout.take_top_with_margin(layout.margin, 0); // extra space
+
+        let [excluded_processes_label_layout, excluded_titles_label_layout] =
+            layout.take_top(label_height * 2).split_horizontal();
+        let excluded_processes_label = gui::Label::new(
+            parent,
+            gui::LabelOpts {
+                text: "Never move windows\r\nof these processes:",
+                position: excluded_processes_label_layout.dpi_pos(),
+                size: excluded_processes_label_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+        let excluded_titles_label = gui::Label::new(
+            parent,
+            gui::LabelOpts {
+                text: "Never move windows\r\nwith these titles:",
+                position: excluded_titles_label_layout.dpi_pos(),
+                size: excluded_titles_label_layout.dpi_size(),
+                ..Default::default()
+            },
+        );
+
+        let [excluded_processes_layout, excluded_titles_layout] =
+            layout.take_top(multi_input_height).split_horizontal();
+        let [excluded_processes_input, excluded_titles_input] =
+            [excluded_processes_layout, excluded_titles_layout].map(|input_layout| {
+                gui::Edit::new(
+                    parent,
+                    gui::EditOpts {
+                        position: input_layout.dpi_pos(),
+                        height: input_layout.dpi_height(),
+                        width: input_layout.dpi_width(),
+                        control_style: co::ES::MULTILINE
+                            | co::ES::WANTRETURN
+                            | co::ES::AUTOVSCROLL
+                            | co::ES::AUTOHSCROLL
+                            | co::ES::NOHIDESEL,
+                        window_style: gui::EditOpts::default().window_style
+                            | co::WS::VSCROLL
+                            | co::WS::HSCROLL,
+                        ..Default::default()
+                    },
+                )
+            });
+
         let new_self = Rc::new(Self {
             start_as_admin_checkbox,
             auto_start_label,
@@ -375,6 +426,10 @@ impl ProgramSettingsPanel {
             menu_at_mouse_pos_hotkey_label_layout,
             menu_at_mouse_pos_hotkey_input,
             menu_at_mouse_pos_hotkey_status,
+            excluded_processes_label,
+            excluded_processes_input,
+            excluded_titles_label,
+            excluded_titles_input,
             tooltips: OnceCell::new(),
             is_manually_setting: Cell::new(0),
         });
@@ -406,6 +461,10 @@ impl ProgramSettingsPanel {
             self.menu_at_mouse_pos_hotkey_label_layout.hwnd(),
             self.menu_at_mouse_pos_hotkey_input.hwnd(),
             self.menu_at_mouse_pos_hotkey_status.hwnd(),
+            self.excluded_processes_label.hwnd(),
+            self.excluded_processes_input.hwnd(),
+            self.excluded_titles_label.hwnd(),
+            self.excluded_titles_input.hwnd(),
         ];
         for handle in handles {
             unsafe { handle.SendMessage(msg.as_generic_wm()) };
@@ -450,6 +509,8 @@ impl ProgramSettingsPanel {
             &self.quick_switch_shortcuts_input,
             &self.menu_at_mouse_pos_hotkey_input,
             &self.quick_switch_hotkey_input,
+            &self.excluded_processes_input,
+            &self.excluded_titles_input,
         ];
         for input in inputs {
             let this = self.clone();
@@ -584,6 +645,18 @@ impl ProgramSettingsPanel {
                 is middle clicked. On some Windows 11 versions middle clicks are \
                 registered as left clicks.",
             )
+            .register(
+                self.excluded_processes_label.native_handle(),
+                "Each line should have the name of a process (without the .exe \
+                extension, case doesn't matter). Windows of these processes are never \
+                moved or changed, even by filters that match all windows.",
+            )
+            .register(
+                self.excluded_titles_label.native_handle(),
+                "Each line should have a regular expression (case doesn't matter). \
+                Windows with a title that matches any of them are never moved or \
+                changed, even by filters that match all windows.",
+            )
             .build(&mut tooltip);
         if let Err(e) = result {
             tracing::error!(error = ?e, "Failed to build tooltips for ProgramSettingsPanel");
@@ -727,6 +800,28 @@ impl ProgramSettingsPanel {
             .map(|index| TrayClickAction::ALL[index as usize])
             .unwrap_or_default()
     }
+    /// Parse the excluded windows. Patterns in `previous` are reused if their
+    /// text wasn't changed so that options that can't be edited here are kept.
+    pub fn get_window_exclusions(&self, previous: &WindowExclusions) -> Arc<WindowExclusions> {
+        let read = |input: &gui::Edit| {
+            input.text().unwrap_or_else(|e| {
+                tracing::error!(error = ?e, "failed to read window exclusions text field");
+                String::new()
+            })
+        };
+        Arc::new(WindowExclusions {
+            process_names: WindowExclusions::patterns_from_lines(
+                &read(&self.excluded_processes_input),
+                &previous.process_names,
+                WindowExclusions::process_name_pattern,
+            ),
+            window_titles: WindowExclusions::patterns_from_lines(
+                &read(&self.excluded_titles_input),
+                &previous.window_titles,
+                WindowExclusions::window_title_pattern,
+            ),
+        })
+    }
 
     pub fn get_settings_data(&self, quick_switch_menu_shortcuts_error: &mut bool) -> UiSettings {
         UiSettings {
@@ -754,6 +849,9 @@ impl ProgramSettingsPanel {
             middle_click: self.get_middle_click(),
             config_window: Default::default(),
             filters: Arc::new([]),
+            filter_groups: Arc::new([]),
+            // Needs the previous value, see `get_window_exclusions`:
+            window_exclusions: Arc::default(),
         }
     }
 }
@@ -939,6 +1037,49 @@ impl ProgramSettingsPanel {
                 .map(|pos| pos as u32),
         )
     }
+    pub fn set_window_exclusions(&self, value: &WindowExclusions) {
+        let warnings = filter_lint::lint_exclusions(value);
+        for warning in &warnings {
+            tracing::warn!(%warning, "Found problem with window exclusions");
+        }
+        let labels = [
+            (
+                &self.excluded_processes_label,
+                "process name",
+                "Never move windows\r\nof these processes:",
+            ),
+            (
+                &self.excluded_titles_label,
+                "window title",
+                "Never move windows\r\nwith these titles:",
+            ),
+        ];
+        for (label, field, text) in labels {
+            let text = if warnings.iter().any(|warning| warning.field == field) {
+                "Invalid pattern, all\r\nwindows excluded:"
+            } else {
+                text
+            };
+            if let Err(e) = label.hwnd().SetWindowText(text) {
+                tracing::error!(error = ?e, "Failed to set window exclusions label");
+            }
+        }
+
+        if *self.get_window_exclusions(value) == *value {
+            return;
+        }
+        let _suppress = self.suppress_events();
+        let inputs = [
+            (&self.excluded_processes_input, &value.process_names),
+            (&self.excluded_titles_input, &value.window_titles),
+        ];
+        for (input, patterns) in inputs {
+            let text = WindowExclusions::patterns_to_lines(patterns);
+            if let Err(e) = input.set_text(&text) {
+                tracing::error!(error = ?e, "Failed to set window exclusions input field");
+            }
+        }
+    }
 
     pub fn set_settings_data(&self, data: &UiSettings) {
         let &UiSettings {
@@ -957,6 +1098,8 @@ impl ProgramSettingsPanel {
             middle_click,
             config_window: _,
             filters: _,
+            filter_groups: _,
+            ref window_exclusions,
         } = data;
 
         self.set_auto_start(auto_start);
@@ -973,6 +1116,7 @@ impl ProgramSettingsPanel {
         self.set_open_menu_at_mouse_pos_hotkey(open_menu_at_mouse_pos_hotkey);
         self.set_left_click(left_click);
         self.set_middle_click(middle_click);
+        self.set_window_exclusions(window_exclusions);
     }
 }
 
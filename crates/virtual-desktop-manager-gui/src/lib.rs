@@ -21,6 +21,7 @@ use std::{
 use virtual_desktop_manager_core::{
     dynamic_gui::DynamicUiHooks,
     exe_icon,
+    filter_lint::{self, FilterWarning},
     nwg_ext::{
         list_view_enable_groups, list_view_item_get_group_id, list_view_item_set_group_id,
         list_view_set_group_info, list_view_sort_rows, window_is_valid, window_placement,
@@ -31,8 +32,11 @@ use virtual_desktop_manager_core::{
     },
     tray::{SystemTray, SystemTrayRef, TrayPlugin},
     vd,
-    window_filter::{ExportedWindowFilters, FilterAction, IntegerRange, TextPattern, WindowFilter},
-    window_info::WindowInfo,
+    window_filter::{
+        ExportedWindowFilters, FilterAction, FilterContext, FilterGroup, IntegerRange,
+        WindowExclusions, WindowFilter,
+    },
+    window_info::{self, WindowInfo},
     ConfigWindowGui,
 };
 
@@ -248,8 +252,8 @@ pub struct ConfigWindow {
 
     #[nwg_control(
         parent: filter_tab, position: (5, 520), size: (230, 25),
-        collection: vec![FilterAction::Move, FilterAction::UnpinAndMove, FilterAction::Unpin, FilterAction::Pin, FilterAction::Nothing, FilterAction::Disabled],
-        selected_index: Some(5),
+        collection: FilterAction::all().to_vec(),
+        selected_index: FilterAction::all().iter().position(|&action| action == FilterAction::Disabled),
     )]
     #[nwg_events(OnComboxBoxSelection: [Self::on_filter_config_ui_changed])]
     filter_action: nwg::ComboBox<FilterAction>,
@@ -425,6 +429,28 @@ pub struct ConfigWindow {
     )]
     settings_open_menu_at_mouse_pos_hotkey_error: nwg::TextBox,
 
+    #[nwg_control(
+        parent: settings_tab, position: (5, 825), size: (117, 40),
+        text: "Never move windows\r\nof these processes:",
+        background_color: TAB_BACKGROUND,
+    )]
+    settings_excluded_processes_label: nwg::Label,
+
+    #[nwg_control(parent: settings_tab, position: (5, 865), size: (117, 85))]
+    #[nwg_events(OnTextInput: [Self::on_settings_ui_changed])]
+    settings_excluded_processes: nwg::TextBox,
+
+    #[nwg_control(
+        parent: settings_tab, position: (128, 825), size: (117, 40),
+        text: "Never move windows\r\nwith these titles:",
+        background_color: TAB_BACKGROUND,
+    )]
+    settings_excluded_titles_label: nwg::Label,
+
+    #[nwg_control(parent: settings_tab, position: (128, 865), size: (117, 85))]
+    #[nwg_events(OnTextInput: [Self::on_settings_ui_changed])]
+    settings_excluded_titles: nwg::TextBox,
+
     #[nwg_control(parent: window, flags: "VISIBLE")]
     utils_frame: nwg::Frame,
 
@@ -446,6 +472,9 @@ pub struct ConfigWindow {
 
     background_thread: RefCell<Option<BackgroundThread>>,
     has_queued_refresh: Cell<bool>,
+    /// Virtual desktop info used when checking filters against windows,
+    /// updated when window info is gathered.
+    filter_context: RefCell<FilterContext>,
     is_data_sorted: Cell<bool>,
 
     #[nwg_control(parent: window)]
@@ -594,6 +623,18 @@ impl ConfigWindow {
                 is middle clicked. On some Windows 11 versions middle clicks are \
                 registered as left clicks.",
             )
+            .register(
+                &self.settings_excluded_processes_label,
+                "Each line should have the name of a process (without the .exe \
+                extension, case doesn't matter). Windows of these processes are never \
+                moved or changed, even by filters that match all windows.",
+            )
+            .register(
+                &self.settings_excluded_titles_label,
+                "Each line should have a regular expression (case doesn't matter). \
+                Windows with a title that matches any of them are never moved or \
+                changed, even by filters that match all windows.",
+            )
             .build(&mut self.tooltips)?;
         Ok(())
     }
@@ -812,6 +853,7 @@ impl ConfigWindow {
         window_index: i32,
         window: &WindowInfo,
     ) -> String {
+        let filter_context = self.filter_context.borrow();
         self.loaded_filters
             .borrow()
             .as_deref()
@@ -819,7 +861,7 @@ impl ConfigWindow {
             .iter()
             .enumerate()
             // Find filters/rules that apply to this window:
-            .filter(|(_, rule)| rule.check_window(window_index, window))
+            .filter(|(_, rule)| rule.check_window(window_index, window, &filter_context))
             // one-based indexes:
             .map(|(ix, _)| (ix + 1).to_string())
             .collect::<Vec<_>>()
@@ -832,20 +874,29 @@ impl ConfigWindow {
             guard.push(window.clone());
             index
         };
+        // Windows are loaded in order, so earlier windows of the same process
+        // are already known:
+        self.filter_context
+            .borrow_mut()
+            .process_windows
+            .add(&window);
 
         let filter_indexes = self.determine_active_filter_indexes_for_window(index as i32, &window);
-        let action = WindowFilter::find_first_action(
+        let action = WindowFilter::display_actions(&WindowFilter::find_actions(
             self.loaded_filters.borrow().as_deref().unwrap_or_default(),
             index as i32,
             &window,
-        )
-        .map(|filter| filter.display_target_desktop().to_string());
+            &self.filter_context.borrow(),
+        ))
+        .to_string();
 
         let WindowInfo {
             handle: _,
             title,
+            class_name: _,
             process_id: _,
             process_name,
+            executable_path: _,
             virtual_desktop,
         } = window;
 
@@ -857,7 +908,7 @@ impl ConfigWindow {
             title.as_str(),
             &*process_name,
             filter_indexes.as_str(),
-            action.as_deref().unwrap_or_default(),
+            action.as_str(),
         ];
         self.data_view.insert_items_row(None, &info);
         list_view_item_set_group_id(
@@ -901,18 +952,19 @@ impl ConfigWindow {
                 },
             );
 
-            let action = WindowFilter::find_first_action(
+            let action = WindowFilter::display_actions(&WindowFilter::find_actions(
                 self.loaded_filters.borrow().as_deref().unwrap_or_default(),
                 window_index as i32,
                 &window_info,
-            )
-            .map(|filter| filter.display_target_desktop().to_string());
+                &self.filter_context.borrow(),
+            ))
+            .to_string();
             self.data_view.update_item(
                 row_ix,
                 nwg::InsertListViewItem {
                     index: Some(row_ix as _),
                     column_index: Self::COLUMN_TARGET_DESKTOP as _,
-                    text: Some(action.unwrap_or_default()),
+                    text: Some(action),
                     image: None,
                 },
             );
@@ -931,6 +983,14 @@ impl ConfigWindow {
         }
         self.clear_window_info();
         self.has_queued_refresh.set(false);
+        let filter_context = window_info::gather_filter_context();
+        let desktop_count_changed =
+            self.filter_context.borrow().desktop_count != filter_context.desktop_count;
+        *self.filter_context.borrow_mut() = filter_context;
+        if desktop_count_changed {
+            // Warnings about target desktops depend on the number of desktops:
+            self.sync_filter_from_settings(None);
+        }
 
         let (tx, rx) = mpsc::channel();
         let notice_tx = self.data_notice.sender();
@@ -950,7 +1010,7 @@ impl ConfigWindow {
                                 );
                             }
                         }
-                        for result in WindowInfo::try_get_all() {
+                        for result in window_info::try_get_all_window_info() {
                             if let Ok(window) = result {
                                 tracing::trace!(info = ?window, "Sending window info to config window");
                                 if tx.send(window).is_err() {
@@ -1150,7 +1210,8 @@ impl ConfigWindow {
             allow_overwrite = false; // <- Since we change the path the dialog would not have warned about overwrite
             false
         };
-        let Some(data) = (if is_legacy {
+        // Warns about filters that had to be exported as disabled:
+        let Some((data, warning)) = (if is_legacy {
             #[cfg(feature = "persist_filters_xml")]
             {
                 let filters = self.loaded_filters.borrow().clone();
@@ -1162,6 +1223,10 @@ impl ConfigWindow {
                         );
                     })
                     .ok()
+                    .map(|exported| {
+                        let warning = exported.warning();
+                        (exported.xml, warning)
+                    })
             }
             #[cfg(not(feature = "persist_filters_xml"))]
             {
@@ -1171,20 +1236,24 @@ impl ConfigWindow {
                     Recompile the program from source with the \"persist_filters_xml\" feature \
                     in order to support exporting such filter files.",
                 );
-                None::<String>
+                None::<(String, Option<String>)>
             }
         } else {
             #[cfg(feature = "persist_filters")]
             {
-                let exported = ExportedWindowFilters {
-                    filters: self
-                        .loaded_filters
+                let groups = self
+                    .tray
+                    .get()
+                    .map(|tray| tray.settings().get().filter_groups.clone())
+                    .unwrap_or_default();
+                let exported = ExportedWindowFilters::new(
+                    self.loaded_filters
                         .borrow()
                         .clone()
                         .unwrap_or_default()
                         .to_vec(),
-                    ..Default::default()
-                };
+                )
+                .with_groups(&groups);
                 serde_json::to_string_pretty(&exported)
                     .inspect_err(|e| {
                         nwg::error_message(
@@ -1193,6 +1262,7 @@ impl ConfigWindow {
                         );
                     })
                     .ok()
+                    .map(|data| (data, None))
             }
             #[cfg(not(feature = "persist_filters"))]
             {
@@ -1230,6 +1300,16 @@ impl ConfigWindow {
                     selected.display()
                 ),
             );
+        } else if let Some(warning) = warning {
+            nwg::modal_message(
+                &self.window,
+                &nwg::MessageParams {
+                    title: "Virtual Desktop Manager - Export warning",
+                    content: &warning,
+                    buttons: nwg::MessageButtons::Ok,
+                    icons: nwg::MessageIcons::Warning,
+                },
+            );
         }
     }
     fn on_import_filters(&self) {
@@ -1280,7 +1360,7 @@ impl ConfigWindow {
             .extension()
             .is_some_and(|ext| ext.eq_ignore_ascii_case("xml") || ext.eq_ignore_ascii_case("txt"));
 
-        let Some(imported) = (if is_legacy {
+        let Some((imported, imported_groups)) = (if is_legacy {
             #[cfg(feature = "persist_filters_xml")]
             {
                 WindowFilter::deserialize_from_xml(&_data)
@@ -1291,6 +1371,7 @@ impl ConfigWindow {
                         );
                     })
                     .ok()
+                    .map(|filters| (filters, Vec::new()))
             }
             #[cfg(not(feature = "persist_filters_xml"))]
             {
@@ -1300,7 +1381,7 @@ impl ConfigWindow {
                     Recompile the program from source with the \"persist_filters_xml\" feature \
                     in order to support such filter files.",
                 );
-                None::<Vec<WindowFilter>>
+                None::<(Vec<WindowFilter>, Vec<FilterGroup>)>
             }
         } else {
             #[cfg(feature = "persist_filters")]
@@ -1324,7 +1405,7 @@ impl ConfigWindow {
                         );
                     })
                     .ok()
-                    .map(|info| info.migrate_and_get_filters())
+                    .map(|info| info.migrate_and_get_filters_with_groups())
             }
             #[cfg(not(feature = "persist_filters"))]
             {
@@ -1344,6 +1425,7 @@ impl ConfigWindow {
         };
         tray.settings().update(|prev| UiSettings {
             filters: prev.filters.iter().cloned().chain(imported).collect(),
+            filter_groups: FilterGroup::merge(&prev.filter_groups, imported_groups),
             ..prev.clone()
         });
     }
@@ -1573,7 +1655,9 @@ impl ConfigWindow {
                 min: 1,
             });
     }
-    fn get_filter_config_for_sidebar(&self) -> Option<WindowFilter> {
+    /// Read the filter options from the sidebar. Options that can't be edited
+    /// in the sidebar are copied from the `prev` filter.
+    fn get_filter_config_for_sidebar(&self, prev: &WindowFilter) -> Option<WindowFilter> {
         Some(WindowFilter {
             window_index: {
                 IntegerRange {
@@ -1627,8 +1711,12 @@ impl ConfigWindow {
                     },
                 }
             },
-            window_title: TextPattern::new(Arc::from(self.filter_title.text().replace('\r', ""))),
-            process_name: TextPattern::new(Arc::from(self.filter_process.text().replace('\r', ""))),
+            window_title: prev
+                .window_title
+                .with_pattern(Arc::from(self.filter_title.text().replace('\r', ""))),
+            process_name: prev
+                .process_name
+                .with_pattern(Arc::from(self.filter_process.text().replace('\r', ""))),
             action: 'action: {
                 let Some(selected) = self.filter_action.selection() else {
                     break 'action FilterAction::default();
@@ -1646,6 +1734,7 @@ impl ConfigWindow {
             } else {
                 return None;
             },
+            ..prev.clone()
         })
     }
 
@@ -1667,7 +1756,10 @@ impl ConfigWindow {
         let Some(tray) = self.tray.get() else {
             return;
         };
-        let Some(new_filter) = self.get_filter_config_for_sidebar() else {
+        let Some(prev_filter) = tray.settings().get().filters.get(index).cloned() else {
+            return;
+        };
+        let Some(new_filter) = self.get_filter_config_for_sidebar(&prev_filter) else {
             return;
         };
 
@@ -1720,7 +1812,7 @@ impl ConfigWindow {
             ..prev.clone()
         });
     }
-    fn populate_filter_list(&self, filters: &Arc<[WindowFilter]>) {
+    fn populate_filter_list(&self, filters: &Arc<[WindowFilter]>, groups: &[FilterGroup]) {
         let prev_filters = self.loaded_filters.borrow().clone();
         let prev_filters = prev_filters.as_deref().unwrap_or_default();
         let mut indexes_to_skip = Vec::with_capacity(prev_filters.len());
@@ -1731,26 +1823,73 @@ impl ConfigWindow {
             "ConfigWindow::populate_filter_list"
         );
 
-        fn get_filter_columns(filter_index: usize, filter: &WindowFilter) -> [String; 6] {
+        fn get_filter_action_column(
+            filter_index: usize,
+            filter: &WindowFilter,
+            warnings: &[FilterWarning],
+        ) -> String {
+            let mut text = filter.display_target_desktop().to_string();
+            let mut filter_warnings = warnings
+                .iter()
+                .filter(|warning| warning.filter_index == filter_index);
+            if let Some(first) = filter_warnings.next() {
+                text.push_str(&format!(" (Warning: {}", first.kind));
+                let more = filter_warnings.count();
+                if more > 0 {
+                    text.push_str(&format!(", +{more} more"));
+                }
+                text.push(')');
+            }
+            text
+        }
+        fn get_filter_columns(
+            filter_index: usize,
+            filter: &WindowFilter,
+            warnings: &[FilterWarning],
+        ) -> [String; 6] {
             let WindowFilter {
-                window_index,
-                desktop_index,
+                name: _,
+                tags: _,
+                group: _,
+                window_index: _,
+                desktop_index: _,
+                window_index_set: _,
+                desktop_index_set: _,
                 window_title,
                 process_name,
+                window_class: _,
+                executable_path: _,
+                conditions: _,
                 action: _,
                 target_desktop: _,
+                dynamic_target: _,
+                create_missing_desktops: _,
+                distribute_to: _,
+                continue_after_match: _,
+                only_first_time: _,
+                settle_delay_ms: _,
+                apply_to_app_pinned: _,
+                focus_after_move: _,
+                stop_flashing: _,
+                notify_on_move: _,
             } = filter;
 
             [
-                window_index.into_one_based_indexes().to_string(),
-                desktop_index.into_one_based_indexes().to_string(),
-                window_title.display_escaped_newline_glob().to_string(),
-                process_name.display_escaped_newline_glob().to_string(),
+                filter.display_window_index().to_string(),
+                filter.display_desktop_index().to_string(),
+                window_title.display_escaped().to_string(),
+                process_name.display_escaped().to_string(),
                 filter_index.saturating_add(1).to_string(),
-                filter.display_target_desktop().to_string(),
+                get_filter_action_column(filter_index, filter, warnings),
             ]
         }
 
+        let warnings =
+            filter_lint::lint_filters(filters, groups, self.filter_context.borrow().desktop_count);
+        for warning in &warnings {
+            tracing::debug!(%warning, "Found problem with filter");
+        }
+
         // Update existing filter items:
         for ix in (0..self.data_view.len()).rev() {
             let group = list_view_item_get_group_id(&self.data_view, ix);
@@ -1764,7 +1903,7 @@ impl ConfigWindow {
                     if let Some(prev) = prev_filters.get(filter_ix) {
                         if let Some(new) = filters.get(filter_ix) {
                             if prev != new {
-                                let info = get_filter_columns(filter_ix, new);
+                                let info = get_filter_columns(filter_ix, new, &warnings);
                                 for (column_ix, text) in info.into_iter().enumerate() {
                                     self.data_view.update_item(
                                         ix,
@@ -1776,6 +1915,19 @@ impl ConfigWindow {
                                         },
                                     );
                                 }
+                            } else {
+                                // Warnings can change even if the filter didn't:
+                                self.data_view.update_item(
+                                    ix,
+                                    nwg::InsertListViewItem {
+                                        index: Some(ix as _),
+                                        column_index: Self::COLUMN_TARGET_DESKTOP as _,
+                                        text: Some(get_filter_action_column(
+                                            filter_ix, new, &warnings,
+                                        )),
+                                        image: None,
+                                    },
+                                );
                             }
                             indexes_to_skip.push(filter_ix);
                             continue;
@@ -1797,7 +1949,7 @@ impl ConfigWindow {
             if indexes_to_skip.contains(&filter_index) {
                 continue;
             }
-            let info = get_filter_columns(filter_index, filter);
+            let info = get_filter_columns(filter_index, filter, &warnings);
             self.data_view.insert_items_row(None, &info);
             list_view_item_set_group_id(
                 &self.data_view,
@@ -1841,7 +1993,7 @@ impl ConfigWindow {
                 &settings_owned
             }
         };
-        self.populate_filter_list(&settings.filters);
+        self.populate_filter_list(&settings.filters, &settings.filter_groups);
     }
 }
 /// Methods related to "Program settings" tab.
@@ -1980,9 +2132,26 @@ impl ConfigWindow {
             left_click,
             middle_click,
             open_menu_at_mouse_pos_hotkey,
+            window_exclusions: self.window_exclusions_from_ui(&prev.window_exclusions),
             ..prev.clone()
         });
     }
+    /// Parse the excluded windows. Patterns in `previous` are reused if their
+    /// text wasn't changed so that options that can't be edited here are kept.
+    fn window_exclusions_from_ui(&self, previous: &WindowExclusions) -> Arc<WindowExclusions> {
+        Arc::new(WindowExclusions {
+            process_names: WindowExclusions::patterns_from_lines(
+                &self.settings_excluded_processes.text(),
+                &previous.process_names,
+                WindowExclusions::process_name_pattern,
+            ),
+            window_titles: WindowExclusions::patterns_from_lines(
+                &self.settings_excluded_titles.text(),
+                &previous.window_titles,
+                WindowExclusions::window_title_pattern,
+            ),
+        })
+    }
     fn sync_program_options_from_settings(&self, settings: Option<&Arc<UiSettings>>) {
         let settings_owned;
         let settings = match settings {
@@ -2110,6 +2279,35 @@ impl ConfigWindow {
                     }
                 });
         }
+        if *self.window_exclusions_from_ui(&settings.window_exclusions)
+            != *settings.window_exclusions
+        {
+            self.settings_excluded_processes
+                .set_text(&WindowExclusions::patterns_to_lines(
+                    &settings.window_exclusions.process_names,
+                ));
+            self.settings_excluded_titles
+                .set_text(&WindowExclusions::patterns_to_lines(
+                    &settings.window_exclusions.window_titles,
+                ));
+        }
+        let warnings = filter_lint::lint_exclusions(&settings.window_exclusions);
+        for warning in &warnings {
+            tracing::warn!(%warning, "Found problem with window exclusions");
+        }
+        let has_warning = |field| warnings.iter().any(|warning| warning.field == field);
+        self.settings_excluded_processes_label
+            .set_text(if has_warning("process name") {
+                "Invalid pattern, all\r\nwindows excluded:"
+            } else {
+                "Never move windows\r\nof these processes:"
+            });
+        self.settings_excluded_titles_label
+            .set_text(if has_warning("window title") {
+                "Invalid pattern, all\r\nwindows excluded:"
+            } else {
+                "Never move windows\r\nwith these titles:"
+            });
     }
     fn sync_quick_shortcuts_from(&self, shortcuts: &BTreeMap<String, u32>) {
         let selection = self.settings_quick_menu_shortcuts.selection();